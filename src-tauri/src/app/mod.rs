@@ -4,8 +4,9 @@
 use tauri::Manager;
 use std::io::{Read, Seek};
 
-use std::sync::atomic::{AtomicBool};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 // 应用状态
 #[derive(Default)]
@@ -16,14 +17,39 @@ pub struct AppState {
     pub processing_count: u32,
 }
 
+#[derive(Default)]
 pub struct ExportState {
-    pub is_cancelled: Arc<AtomicBool>,
+    // 各导出任务 (单个视频导出、批量处理) 的取消标记，按任务 ID 区分，互不影响
+    tasks: Mutex<HashMap<String, Arc<AtomicBool>>>,
 }
 
-impl Default for ExportState {
-    fn default() -> Self {
-        Self {
-            is_cancelled: Arc::new(AtomicBool::new(false)),
+impl ExportState {
+    // 登记任务并返回它专属的取消标记，同 ID 的旧任务仍持有自己的标记
+    pub fn begin(&self, id: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        if let Ok(mut tasks) = self.tasks.lock() {
+            tasks.insert(id.to_string(), flag.clone());
+        }
+        flag
+    }
+
+    // 取消指定任务，任务不存在时返回 false
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.tasks.lock().ok().and_then(|tasks| tasks.get(id).cloned()) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 任务结束后注销，只移除仍属于该任务的标记
+    pub fn finish(&self, id: &str, flag: &Arc<AtomicBool>) {
+        if let Ok(mut tasks) = self.tasks.lock() {
+            if tasks.get(id).is_some_and(|current| Arc::ptr_eq(current, flag)) {
+                tasks.remove(id);
+            }
         }
     }
 }
@@ -73,6 +99,25 @@ pub fn run_app() -> tauri::Result<()> {
                 log::error!("❌ 未找到任何 FFmpeg/FFprobe！应用功能将受限。");
             }
 
+            // 磁盘分析缓存、导出任务队列与监视文件夹配置放在应用数据目录下
+            let data_dir = match app.path().app_data_dir() {
+                Ok(data_dir) => Some(data_dir),
+                Err(e) => {
+                    log::warn!("无法获取应用数据目录，磁盘分析缓存与任务队列持久化不可用: {}", e);
+                    None
+                }
+            };
+            if let Some(data_dir) = &data_dir {
+                crate::audio::disk_cache::init(data_dir.join("analysis_cache"));
+            }
+            crate::jobs::init(
+                app.handle().clone(),
+                data_dir.as_ref().map(|d| d.join("jobs.json")),
+                ffmpeg_path.clone(),
+                ffprobe_path.clone(),
+            );
+            crate::watch::init(app.handle().clone(), data_dir);
+
             app.manage(AppState {
                 ffmpeg_available,
                 ffmpeg_path: ffmpeg_path.map(std::path::PathBuf::from),
@@ -270,12 +315,28 @@ pub fn run_app() -> tauri::Result<()> {
             crate::commands::get_video_info,
             crate::commands::extract_audio,
             crate::commands::detect_silences,
+            crate::commands::analyze_audio_levels,
+            crate::commands::get_waveform_range,
+            crate::commands::release_audio_cache,
+            crate::commands::cache_stats,
             crate::commands::process_video,
             crate::commands::cancel_export,
             crate::commands::start_upload,
             crate::commands::upload_chunk,
             crate::commands::finish_upload,
             crate::commands::batch_process,
+            crate::commands::cancel_batch,
+            crate::commands::submit_job,
+            crate::commands::list_jobs,
+            crate::commands::get_job,
+            crate::commands::cancel_job,
+            crate::commands::pause_job,
+            crate::commands::resume_job,
+            crate::commands::remove_finished_jobs,
+            crate::commands::start_watch_folder,
+            crate::commands::stop_watch_folder,
+            crate::commands::list_watch_folders,
+            crate::commands::get_watch_ledger,
             crate::commands::reveal_in_explorer,
         ]);
     
@@ -0,0 +1,341 @@
+// 内存音频缓存
+// 样本以 16-bit 存储，总大小超过上限时按最近使用时间淘汰 (LRU)
+
+use super::waveform::PeakPyramid;
+use super::{analysis_window_size, window_energies};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+// 默认内存上限 1GB (16kHz 单声道约可容纳 9 小时音频)
+pub const DEFAULT_MAX_BYTES: usize = 1024 * 1024 * 1024;
+
+// 缓存中的音频数据，按声道分别存储
+pub struct CachedAudio {
+    pub channels: Vec<Vec<i16>>,
+    pub sample_rate: u32,
+    // 多分辨率波形峰值
+    pub pyramid: PeakPyramid,
+    // 逐窗口能量包络，只调阈值时重新检测无需再扫描样本
+    pub envelope: Envelope,
+}
+
+// 每个分析窗口 (20ms) 的 RMS 能量
+pub struct Envelope {
+    pub window_size: usize,
+    pub channels: Vec<Vec<f64>>,
+    // 多声道时的混合声道包络，单声道时与 channels[0] 相同不重复存储
+    mixdown: Option<Vec<f64>>,
+}
+
+impl Envelope {
+    pub fn mixdown(&self) -> &[f64] {
+        self.mixdown.as_deref().unwrap_or_else(|| self.channels.first().map_or(&[], |c| c.as_slice()))
+    }
+
+    fn size_bytes(&self) -> usize {
+        let windows: usize = self.channels.iter().chain(&self.mixdown).map(|e| e.len()).sum();
+        windows * std::mem::size_of::<f64>()
+    }
+}
+
+impl CachedAudio {
+    pub fn new(channels: Vec<Vec<i16>>, sample_rate: u32) -> Self {
+        let pyramid = PeakPyramid::build(&channels);
+        let mut audio = Self {
+            channels,
+            sample_rate,
+            pyramid,
+            envelope: Envelope { window_size: 0, channels: Vec::new(), mixdown: None },
+        };
+        audio.envelope = audio.build_envelope();
+        audio
+    }
+
+    fn build_envelope(&self) -> Envelope {
+        let window_size = analysis_window_size(self.sample_rate);
+        if window_size == 0 {
+            return Envelope { window_size, channels: Vec::new(), mixdown: None };
+        }
+        let frames = self.channels.iter().map(|c| c.len()).min().unwrap_or(0);
+        Envelope {
+            window_size,
+            channels: self.channels.iter().map(|c| window_energies(&c[..frames], window_size)).collect(),
+            mixdown: if self.channels.len() > 1 {
+                Some(window_energies(&self.mixdown(), window_size))
+            } else {
+                None
+            },
+        }
+    }
+
+    // 各声道取平均混成单声道，单声道时直接借用
+    pub fn mixdown(&self) -> Cow<'_, [i16]> {
+        if self.channels.len() == 1 {
+            return Cow::Borrowed(&self.channels[0]);
+        }
+        let len = self.channels.iter().map(|c| c.len()).min().unwrap_or(0);
+        let count = self.channels.len().max(1) as i32;
+        Cow::Owned((0..len)
+            .map(|i| (self.channels.iter().map(|c| c[i] as i32).sum::<i32>() / count) as i16)
+            .collect())
+    }
+
+    fn size_bytes(&self) -> usize {
+        self.channels.iter().map(|c| c.len() * std::mem::size_of::<i16>()).sum::<usize>()
+            + self.pyramid.size_bytes()
+            + self.envelope.size_bytes()
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.channels.first().map_or(0, |c| c.len()) as f64 / self.sample_rate.max(1) as f64
+    }
+}
+
+struct CacheEntry {
+    audio: CachedAudio,
+    bytes: usize,
+    last_used: u64,
+}
+
+// 单个缓存条目的概况
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEntryStats {
+    pub cache_id: String,
+    pub channels: usize,
+    pub sample_rate: u32,
+    pub duration: f64,
+    pub bytes: usize,
+}
+
+// 缓存整体状态
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub entries: Vec<CacheEntryStats>,
+    pub total_bytes: usize,
+    pub max_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+pub struct AudioCache {
+    entries: HashMap<String, CacheEntry>,
+    total_bytes: usize,
+    max_bytes: usize,
+    // 逻辑时钟，每次访问递增，用于 LRU 排序
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl AudioCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            total_bytes: 0,
+            max_bytes,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    // 插入或替换条目，超出上限时淘汰最久未使用的其他条目
+    // 新条目本身即使超过上限也会保留，保证刚提取的数据可用
+    pub fn insert(&mut self, cache_id: String, audio: CachedAudio) {
+        self.remove(&cache_id);
+        self.clock += 1;
+        let bytes = audio.size_bytes();
+        self.total_bytes += bytes;
+        self.entries.insert(cache_id.clone(), CacheEntry { audio, bytes, last_used: self.clock });
+        self.evict_except(&cache_id);
+    }
+
+    // 读取并刷新最近使用时间
+    pub fn get(&mut self, cache_id: &str) -> Option<&CachedAudio> {
+        self.clock += 1;
+        match self.entries.get_mut(cache_id) {
+            Some(entry) => {
+                self.hits += 1;
+                entry.last_used = self.clock;
+                Some(&entry.audio)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    // 只读访问，不影响淘汰顺序
+    pub fn peek(&self, cache_id: &str) -> Option<&CachedAudio> {
+        self.entries.get(cache_id).map(|e| &e.audio)
+    }
+
+    pub fn contains_key(&self, cache_id: &str) -> bool {
+        self.entries.contains_key(cache_id)
+    }
+
+    pub fn remove(&mut self, cache_id: &str) -> Option<CachedAudio> {
+        let entry = self.entries.remove(cache_id)?;
+        self.total_bytes -= entry.bytes;
+        Some(entry.audio)
+    }
+
+    // 只释放指定条目，不影响同一文件的其它条目，返回释放的字节数
+    pub fn release_exact(&mut self, cache_id: &str) -> usize {
+        let before = self.total_bytes;
+        self.remove(cache_id);
+        before - self.total_bytes
+    }
+
+    // 释放指定视频的缓存 (包括 "路径#..." 形式的派生条目：其他音轨、外部音频)
+    // cache_id 为 None 时清空全部，返回释放的字节数
+    pub fn release(&mut self, cache_id: Option<&str>) -> usize {
+        let before = self.total_bytes;
+        match cache_id {
+            Some(id) => {
+                let prefix = format!("{}#", id);
+                let ids: Vec<String> = self.entries.keys()
+                    .filter(|k| k.as_str() == id || k.starts_with(&prefix))
+                    .cloned()
+                    .collect();
+                for id in ids {
+                    self.remove(&id);
+                }
+            }
+            None => {
+                self.entries.clear();
+                self.total_bytes = 0;
+            }
+        }
+        before - self.total_bytes
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let mut entries: Vec<CacheEntryStats> = self.entries.iter()
+            .map(|(id, entry)| CacheEntryStats {
+                cache_id: id.clone(),
+                channels: entry.audio.channels.len(),
+                sample_rate: entry.audio.sample_rate,
+                duration: entry.audio.duration(),
+                bytes: entry.bytes,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.cache_id.cmp(&b.cache_id));
+
+        CacheStats {
+            entries,
+            total_bytes: self.total_bytes,
+            max_bytes: self.max_bytes,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+
+    fn evict_except(&mut self, keep: &str) {
+        while self.total_bytes > self.max_bytes {
+            let oldest = self.entries.iter()
+                .filter(|(id, _)| id.as_str() != keep)
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(id, _)| id.clone());
+            match oldest {
+                Some(id) => {
+                    if let Some(entry) = self.entries.remove(&id) {
+                        self.total_bytes -= entry.bytes;
+                        self.evictions += 1;
+                        eprintln!("🧹 音频缓存超出上限，淘汰: {} ({} bytes)", id, entry.bytes);
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audio() -> CachedAudio {
+        CachedAudio::new(vec![vec![100i16; 1600]], 16000)
+    }
+
+    fn unit() -> usize {
+        audio().size_bytes()
+    }
+
+    fn ids(cache: &AudioCache) -> Vec<String> {
+        cache.stats().entries.into_iter().map(|e| e.cache_id).collect()
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = AudioCache::new(unit() * 5 / 2);
+        cache.insert("a".into(), audio());
+        cache.insert("b".into(), audio());
+        assert!(cache.get("a").is_some());
+        cache.insert("c".into(), audio());
+
+        assert_eq!(ids(&cache), vec!["a", "c"]);
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.stats().total_bytes, unit() * 2);
+    }
+
+    #[test]
+    fn peek_does_not_refresh() {
+        let mut cache = AudioCache::new(unit() * 5 / 2);
+        cache.insert("a".into(), audio());
+        cache.insert("b".into(), audio());
+        assert!(cache.peek("a").is_some());
+        cache.insert("c".into(), audio());
+        assert_eq!(ids(&cache), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn new_entry_over_limit_is_kept() {
+        let mut cache = AudioCache::new(unit() / 2);
+        cache.insert("a".into(), audio());
+        cache.insert("b".into(), audio());
+        assert_eq!(ids(&cache), vec!["b"]);
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn reinserting_replaces_without_double_counting() {
+        let mut cache = AudioCache::new(unit() * 10);
+        cache.insert("a".into(), audio());
+        cache.insert("a".into(), audio());
+        assert_eq!(cache.stats().total_bytes, unit());
+    }
+
+    #[test]
+    fn release_drops_derived_entries_of_same_file() {
+        let mut cache = AudioCache::new(unit() * 10);
+        for id in ["v.mp4", "v.mp4#a:1", "v.mp4#ext:mic.wav", "v.mp42", "w.mp4"] {
+            cache.insert(id.into(), audio());
+        }
+        assert_eq!(cache.release(Some("v.mp4")), unit() * 3);
+        assert_eq!(ids(&cache), vec!["v.mp42", "w.mp4"]);
+
+        assert_eq!(cache.release(None), unit() * 2);
+        assert!(ids(&cache).is_empty());
+        assert_eq!(cache.stats().total_bytes, 0);
+    }
+
+    #[test]
+    fn release_exact_keeps_other_entries_of_same_file() {
+        let mut cache = AudioCache::new(unit() * 10);
+        cache.insert("v.mp4".into(), audio());
+        cache.insert("v.mp4#a:1".into(), audio());
+        assert_eq!(cache.release_exact("v.mp4#a:1"), unit());
+        assert_eq!(cache.release_exact("v.mp4#a:1"), 0);
+        assert_eq!(ids(&cache), vec!["v.mp4"]);
+    }
+}
@@ -0,0 +1,304 @@
+// 磁盘分析缓存
+// 以文件内容指纹 + 采样率 + 音轨选择为键，保存 16-bit 样本与波形峰值，
+// 重新打开同一文件时可以跳过 ffmpeg 提取
+
+use once_cell::sync::OnceCell;
+use std::fs;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+static CACHE_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+const MAGIC: &[u8; 4] = b"SCAC";
+// 2: 多声道不再下混为立体声，旧版本文件中的声道数与源音频不符
+const VERSION: u32 = 2;
+// 指纹采样块大小
+const FINGERPRINT_BLOCK: u64 = 1024 * 1024;
+// 缓存目录总大小上限，超出后删除最早写入的文件
+const MAX_CACHE_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+// 从磁盘读出的分析数据
+pub struct CachedAnalysis {
+    pub channels: Vec<Vec<i16>>,
+    pub peaks: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+// 设置缓存目录 (应用启动时调用一次)
+pub fn init(dir: PathBuf) {
+    if let Err(e) = fs::create_dir_all(&dir) {
+        log::warn!("创建分析缓存目录失败: {:?} -> {}", dir, e);
+        return;
+    }
+    eprintln!("💾 分析缓存目录: {:?}", dir);
+    let _ = CACHE_DIR.set(dir);
+}
+
+// 计算缓存键：文件大小 + 开头/中间/结尾各 1MB 内容的 FNV-1a 哈希，再混入采样率与音轨选择
+pub fn cache_key(path: &str, sample_rate: u32, variant: &str) -> Option<String> {
+    CACHE_DIR.get()?;
+
+    let mut file = fs::File::open(path).ok()?;
+    let size = file.metadata().ok()?.len();
+
+    let mut hash = Fnv1a::new();
+    hash.write(&size.to_le_bytes());
+    let mut buffer = vec![0u8; FINGERPRINT_BLOCK as usize];
+    for offset in [0, size / 2, size.saturating_sub(FINGERPRINT_BLOCK)] {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let n = read_up_to(&mut file, &mut buffer).ok()?;
+        hash.write(&buffer[..n]);
+    }
+    hash.write(&sample_rate.to_le_bytes());
+    hash.write(variant.as_bytes());
+
+    Some(format!("{:016x}", hash.finish()))
+}
+
+// 读取缓存，不存在或损坏时返回 None
+pub fn load(key: &str) -> Option<CachedAnalysis> {
+    load_from(CACHE_DIR.get()?, key)
+}
+
+fn load_from(dir: &Path, key: &str) -> Option<CachedAnalysis> {
+    let path = entry_path(dir, key);
+    let bytes = fs::read(&path).ok()?;
+    match decode(&bytes) {
+        Some(analysis) => Some(analysis),
+        None => {
+            log::warn!("分析缓存文件损坏，已删除: {:?}", path);
+            let _ = fs::remove_file(&path);
+            None
+        }
+    }
+}
+
+// 写入缓存：先写临时文件再重命名，避免留下半截文件
+pub fn store(key: &str, channels: &[Vec<i16>], peaks: &[f32], sample_rate: u32) -> std::io::Result<()> {
+    match CACHE_DIR.get() {
+        Some(dir) => store_in(dir, key, channels, peaks, sample_rate),
+        None => Ok(()),
+    }
+}
+
+fn store_in(dir: &Path, key: &str, channels: &[Vec<i16>], peaks: &[f32], sample_rate: u32) -> std::io::Result<()> {
+    let path = entry_path(dir, key);
+    let temp_path = path.with_extension("tmp");
+    let frames = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+
+    {
+        let mut writer = BufWriter::new(fs::File::create(&temp_path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(channels.len() as u32).to_le_bytes())?;
+        writer.write_all(&(frames as u64).to_le_bytes())?;
+        writer.write_all(&(peaks.len() as u64).to_le_bytes())?;
+        for peak in peaks {
+            writer.write_all(&peak.to_le_bytes())?;
+        }
+        // 样本按声道交错存为 16-bit
+        for i in 0..frames {
+            for channel in channels {
+                writer.write_all(&channel[i].to_le_bytes())?;
+            }
+        }
+        writer.flush()?;
+    }
+
+    fs::rename(&temp_path, &path)?;
+    prune(dir, MAX_CACHE_BYTES);
+    Ok(())
+}
+
+fn entry_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}.scac", key))
+}
+
+fn decode(bytes: &[u8]) -> Option<CachedAnalysis> {
+    let mut reader = ByteReader { bytes, pos: 0 };
+    if reader.take(4)? != MAGIC || reader.u32()? != VERSION {
+        return None;
+    }
+    let sample_rate = reader.u32()?;
+    let channel_count = reader.u32()? as usize;
+    let frames = reader.u64()? as usize;
+    let peak_count = reader.u64()? as usize;
+    if channel_count == 0 {
+        return None;
+    }
+
+    let peak_bytes = reader.take(peak_count.checked_mul(4)?)?;
+    let peaks = peak_bytes.chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+
+    let sample_bytes = reader.take(frames.checked_mul(channel_count)?.checked_mul(2)?)?;
+    let mut channels: Vec<Vec<i16>> = (0..channel_count).map(|_| Vec::with_capacity(frames)).collect();
+    for (i, b) in sample_bytes.chunks_exact(2).enumerate() {
+        channels[i % channel_count].push(i16::from_le_bytes([b[0], b[1]]));
+    }
+
+    Some(CachedAnalysis {
+        channels,
+        peaks,
+        sample_rate,
+    })
+}
+
+// 缓存目录超过上限时，按修改时间从旧到新删除
+fn prune(dir: &Path, max_bytes: u64) {
+    let mut entries: Vec<(PathBuf, u64, std::time::SystemTime)> = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir
+            .filter_map(|e| e.ok())
+            .filter(|e| is_cache_file(&e.path()))
+            .filter_map(|e| {
+                let meta = e.metadata().ok()?;
+                Some((e.path(), meta.len(), meta.modified().ok()?))
+            })
+            .collect(),
+        Err(_) => return,
+    };
+
+    let mut total: u64 = entries.iter().map(|e| e.1).sum();
+    if total <= max_bytes {
+        return;
+    }
+    entries.sort_by_key(|e| e.2);
+    for (path, size, _) in entries {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            eprintln!("🧹 清理分析缓存: {:?}", path);
+            total -= size;
+        }
+    }
+}
+
+fn is_cache_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "scac")
+}
+
+fn read_up_to(file: &mut fs::File, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let b = self.take(8)?;
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(b);
+        Some(u64::from_le_bytes(buf))
+    }
+}
+
+// 64 位 FNV-1a，结果跨版本稳定 (标准库 DefaultHasher 不保证)
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels() -> Vec<Vec<i16>> {
+        vec![(0..1000).map(|i| i as i16).collect(), (0..1000).map(|i| -(i as i16)).collect()]
+    }
+
+    fn dir_bytes(dir: &Path) -> u64 {
+        fs::read_dir(dir).unwrap().map(|e| e.unwrap().metadata().unwrap().len()).sum()
+    }
+
+    #[test]
+    fn round_trips_channels_and_peaks() {
+        let dir = tempfile::tempdir().unwrap();
+        let peaks = [0.0, 0.5, -1.0];
+        store_in(dir.path(), "k", &channels(), &peaks, 16000).unwrap();
+
+        let analysis = load_from(dir.path(), "k").unwrap();
+        assert_eq!(analysis.channels, channels());
+        assert_eq!(analysis.peaks, peaks);
+        assert_eq!(analysis.sample_rate, 16000);
+        assert!(!entry_path(dir.path(), "k").with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn missing_entry_is_none() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_from(dir.path(), "missing").is_none());
+    }
+
+    #[test]
+    fn damaged_files_are_rejected_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        store_in(dir.path(), "k", &channels(), &[0.5], 16000).unwrap();
+        let bytes = fs::read(entry_path(dir.path(), "k")).unwrap();
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        let mut bad_version = bytes.clone();
+        bad_version[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        let truncated = bytes[..bytes.len() - 1].to_vec();
+
+        for (key, content) in [("magic", bad_magic), ("version", bad_version), ("truncated", truncated)] {
+            fs::write(entry_path(dir.path(), key), content).unwrap();
+            assert!(load_from(dir.path(), key).is_none(), "{}", key);
+            assert!(!entry_path(dir.path(), key).exists(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn prune_stays_under_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        for key in ["a", "b", "c", "d"] {
+            store_in(dir.path(), key, &channels(), &[], 16000).unwrap();
+        }
+        // 非缓存文件不计入也不删除
+        fs::write(dir.path().join("other.txt"), vec![0u8; 100_000]).unwrap();
+        let entry_bytes = fs::metadata(entry_path(dir.path(), "a")).unwrap().len();
+
+        prune(dir.path(), entry_bytes * 2);
+        assert!(dir_bytes(dir.path()) - 100_000 <= entry_bytes * 2);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().filter(|e| is_cache_file(&e.as_ref().unwrap().path())).count(), 2);
+        assert!(dir.path().join("other.txt").exists());
+    }
+}
@@ -0,0 +1,205 @@
+// 流式增量静音检测
+// 提取音频的同时逐窗口判定，静音段确定后立即交给前端作为临时剪辑建议。
+// 判定规则与阈值模式的 detect_silences 一致 (同样的分析窗口、最小静音时长、合并间隔与边距)，
+// 其它检测模式也按单阈值预览，提取完成后前端仍以 detect_silences 的结果为准
+
+use super::{analysis_window_size, apply_padding, db_to_linear, linear_to_db, merge_close_silences, merge_gap, DetectionOptions, SilenceSegment};
+
+pub struct IncrementalDetector {
+    sample_rate: u32,
+    window_size: usize,
+    threshold_db: f64,
+    threshold_linear: f64,
+    min_silence_samples: usize,
+    merge_gap: f64,
+    pad_before: f64,
+    pad_after: f64,
+    // 当前窗口的平方和与样本数
+    window_sum: f64,
+    window_count: usize,
+    windows_done: usize,
+    // 当前静音段的起始窗口与能量累计
+    run_start: Option<usize>,
+    run_energy: f64,
+    // 已结束但可能与下一段合并的静音段
+    pending: Option<SilenceSegment>,
+    ready: Vec<SilenceSegment>,
+}
+
+impl IncrementalDetector {
+    pub fn new(sample_rate: u32, threshold_db: f64, min_silence_duration: f64, options: &DetectionOptions) -> Self {
+        Self {
+            sample_rate,
+            window_size: analysis_window_size(sample_rate).max(1),
+            threshold_db,
+            threshold_linear: db_to_linear(threshold_db),
+            min_silence_samples: (min_silence_duration.max(0.0) * sample_rate as f64) as usize,
+            merge_gap: merge_gap(options),
+            pad_before: options.pad_before.max(0.0),
+            pad_after: options.pad_after.max(0.0),
+            window_sum: 0.0,
+            window_count: 0,
+            windows_done: 0,
+            run_start: None,
+            run_energy: 0.0,
+            pending: None,
+            ready: Vec::new(),
+        }
+    }
+
+    // 输入一帧 (各声道已混为单声道) 的样本
+    pub fn push(&mut self, sample: f32) {
+        self.window_sum += (sample as f64).powi(2);
+        self.window_count += 1;
+        if self.window_count < self.window_size {
+            return;
+        }
+
+        let energy = (self.window_sum / self.window_count as f64).sqrt();
+        self.window_sum = 0.0;
+        self.window_count = 0;
+
+        if energy < self.threshold_linear {
+            if self.run_start.is_none() {
+                self.run_start = Some(self.windows_done);
+                self.run_energy = 0.0;
+            }
+            self.run_energy += energy;
+        } else {
+            self.close_run(self.windows_done * self.window_size);
+        }
+        self.windows_done += 1;
+
+        // 语音已持续超过合并间隔，待定片段不会再变化
+        let now = (self.windows_done * self.window_size) as f64 / self.sample_rate as f64;
+        if self.run_start.is_none() && self.pending.is_some_and(|p| now - p.end_time > self.merge_gap) {
+            if let Some(pending) = self.pending.take() {
+                self.release(pending, f64::INFINITY);
+            }
+        }
+    }
+
+    // 取出目前已确定的静音段
+    pub fn take_ready(&mut self) -> Vec<SilenceSegment> {
+        std::mem::take(&mut self.ready)
+    }
+
+    // 输入结束，返回剩余的所有静音段
+    pub fn finish(mut self, total_samples: usize) -> Vec<SilenceSegment> {
+        if self.window_count > 0 {
+            let energy = (self.window_sum / self.window_count as f64).sqrt();
+            if energy < self.threshold_linear {
+                if self.run_start.is_none() {
+                    self.run_start = Some(self.windows_done);
+                    self.run_energy = 0.0;
+                }
+                self.run_energy += energy;
+            } else {
+                self.close_run(self.windows_done * self.window_size);
+            }
+            self.windows_done += 1;
+        }
+        self.close_run(total_samples);
+        if let Some(pending) = self.pending.take() {
+            self.release(pending, total_samples as f64 / self.sample_rate as f64);
+        }
+        self.ready
+    }
+
+    // 静音段不再变化，收缩边距后交给前端
+    fn release(&mut self, silence: SilenceSegment, total_duration: f64) {
+        self.ready.extend(apply_padding(vec![silence], self.pad_before, self.pad_after, total_duration));
+    }
+
+    fn close_run(&mut self, end_sample: usize) {
+        let first = match self.run_start.take() {
+            Some(first) => first,
+            None => return,
+        };
+        let start_sample = first * self.window_size;
+        if end_sample.saturating_sub(start_sample) < self.min_silence_samples {
+            return;
+        }
+
+        let windows = (self.windows_done - first).max(1) as f64;
+        let mut silence = SilenceSegment::new(
+            start_sample as f64 / self.sample_rate as f64,
+            end_sample as f64 / self.sample_rate as f64,
+            linear_to_db(self.run_energy / windows),
+        );
+        silence.threshold_db = Some(self.threshold_db);
+
+        match self.pending.take() {
+            Some(prev) => {
+                let mut merged = merge_close_silences(vec![prev, silence], self.merge_gap);
+                self.pending = merged.pop();
+                for done in merged {
+                    self.release(done, f64::INFINITY);
+                }
+            }
+            None => self.pending = Some(silence),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audio::detect_silences;
+    use super::*;
+
+    const RATE: u32 = 1000;
+
+    // 按 (时长, 幅度) 依次拼接的测试信号
+    fn signal(parts: &[(f64, f32)]) -> Vec<f32> {
+        parts.iter()
+            .flat_map(|&(secs, level)| std::iter::repeat(level).take((secs * RATE as f64).round() as usize))
+            .collect()
+    }
+
+    fn spans(silences: &[SilenceSegment]) -> Vec<(f64, f64)> {
+        silences.iter().map(|s| ((s.start_time * 1000.0).round() / 1000.0, (s.end_time * 1000.0).round() / 1000.0)).collect()
+    }
+
+    fn options() -> DetectionOptions {
+        DetectionOptions { pad_before: 0.05, pad_after: 0.05, min_speech_duration: 0.1, ..DetectionOptions::default() }
+    }
+
+    // 1-2 秒与 2.06-3 秒之间的 60ms 语音孤岛会被合并，末尾静音延伸到结尾不收缩
+    fn speech_with_pauses() -> Vec<f32> {
+        signal(&[(1.0, 0.5), (1.0, 0.0), (0.06, 0.5), (0.94, 0.0), (1.0, 0.5), (0.6, 0.0)])
+    }
+
+    #[test]
+    fn matches_full_detection() {
+        let samples = speech_with_pauses();
+        let mut detector = IncrementalDetector::new(RATE, -40.0, 0.3, &options());
+        for &s in &samples {
+            detector.push(s);
+        }
+        let incremental = detector.finish(samples.len());
+
+        let full = detect_silences("incremental-test", Some(&samples), RATE, -40.0, 0.3, &options()).unwrap();
+        assert_eq!(spans(&incremental), vec![(1.05, 2.95), (4.05, 4.6)]);
+        assert_eq!(spans(&incremental), spans(&full));
+    }
+
+    #[test]
+    fn releases_segments_once_speech_exceeds_merge_gap() {
+        let samples = speech_with_pauses();
+        let mut detector = IncrementalDetector::new(RATE, -40.0, 0.3, &options());
+        // 3.05 秒时语音只持续了 50ms，静音段仍可能与下一段合并
+        for &s in &samples[..3050] {
+            detector.push(s);
+        }
+        assert!(detector.take_ready().is_empty());
+        for &s in &samples[3050..3200] {
+            detector.push(s);
+        }
+        assert_eq!(spans(&detector.take_ready()), vec![(1.05, 2.95)]);
+
+        for &s in &samples[3200..] {
+            detector.push(s);
+        }
+        assert_eq!(spans(&detector.finish(samples.len())), vec![(4.05, 4.6)]);
+    }
+}
@@ -2,19 +2,119 @@
 // 音频处理模块 - 更新版本
 
 use serde::{Deserialize, Serialize};
-use serde_json;
 use std::process::Command;
 use tempfile::NamedTempFile;
 use std::fs;
-use std::collections::HashMap;
+use std::borrow::Cow;
 use std::sync::{Arc, Mutex};
 use once_cell::sync::Lazy;
+use crate::utils::progress::{NoProgress, ProgressEvent, ProgressSink, StageProgress, WaveformDone, WaveformStep};
+
+mod vad;
+mod sync;
+mod cache;
+mod waveform;
+mod incremental;
+pub mod disk_cache;
+
+use cache::{AudioCache, CachedAudio};
+pub use cache::CacheStats;
+pub use waveform::WaveformRange;
 
 // 全局音频数据缓存，避免大数据通过 IPC 传输
-static AUDIO_CACHE: Lazy<Arc<Mutex<HashMap<String, Vec<f32>>>>> = Lazy::new(|| {
-    Arc::new(Mutex::new(HashMap::new()))
+static AUDIO_CACHE: Lazy<Arc<Mutex<AudioCache>>> = Lazy::new(|| {
+    Arc::new(Mutex::new(AudioCache::new(cache::DEFAULT_MAX_BYTES)))
 });
 
+// 样本类型：缓存中为 16-bit，前端传入的备选数据为 f32
+pub trait Sample: Copy {
+    fn to_f32(self) -> f32;
+}
+
+impl Sample for f32 {
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+impl Sample for i16 {
+    fn to_f32(self) -> f32 {
+        self as f32 / 32768.0
+    }
+}
+
+fn f32_to_i16(sample: f32) -> i16 {
+    (sample * 32768.0).round().clamp(-32768.0, 32767.0) as i16
+}
+
+// 释放内存中的分析数据 (None 表示全部)，返回释放的字节数
+pub fn release_cache(cache_id: Option<&str>) -> usize {
+    let freed = AUDIO_CACHE.lock().map(|mut c| c.release(cache_id)).unwrap_or(0);
+    eprintln!("释放音频缓存: {:?}, {} bytes", cache_id, freed);
+    freed
+}
+
+// 只释放单个缓存条目，同一文件的其它音轨、外部音频等条目保留
+pub fn release_cache_entry(cache_id: &str) -> usize {
+    AUDIO_CACHE.lock().map(|mut c| c.release_exact(cache_id)).unwrap_or(0)
+}
+
+// 指定文件的音频流是否已在内存缓存中 (例如正在编辑器中打开)
+pub fn is_cached(video_path: &str, stream: &AudioStreamSelector) -> bool {
+    AUDIO_CACHE.lock().map(|c| c.contains_key(&stream.cache_id(video_path))).unwrap_or(false)
+}
+
+// 内存缓存状态
+pub fn cache_stats() -> CacheStats {
+    AUDIO_CACHE.lock().unwrap().stats()
+}
+
+// 按时间区间与像素数取波形 min/max，供时间轴任意缩放级别渲染
+pub fn waveform_range(
+    cache_id: &str,
+    start: f64,
+    end: f64,
+    pixels: usize,
+) -> Result<WaveformRange, Box<dyn std::error::Error>> {
+    if pixels == 0 || pixels > 100_000 {
+        return Err("像素数必须在 1 ~ 100000 之间".into());
+    }
+    if start.is_nan() || end.is_nan() || start < 0.0 || end <= start {
+        return Err("无效的时间区间".into());
+    }
+    
+    let mut cache = AUDIO_CACHE.lock().unwrap();
+    let cached = cache.get(cache_id).ok_or("音频缓存不存在，请重新提取音频")?;
+    let sample_rate = cached.sample_rate as f64;
+    let total_frames = cached.channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    
+    // 超出音频长度的部分按静音处理：区间裁剪到音频范围内，像素数按比例缩减
+    let start_frame = ((start * sample_rate) as usize).min(total_frames);
+    let end_frame = ((end * sample_rate).ceil() as usize).min(total_frames);
+    let requested_frames = (end - start) * sample_rate;
+    let frames_per_pixel = requested_frames / pixels as f64;
+    
+    let mut min = Vec::with_capacity(pixels);
+    let mut max = Vec::with_capacity(pixels);
+    if end_frame > start_frame {
+        let covered = (((end_frame - start_frame) as f64 / frames_per_pixel).round() as usize).clamp(1, pixels);
+        let (lo, hi) = cached.pyramid.range(&cached.channels, start_frame, end_frame, covered);
+        min.extend(lo);
+        max.extend(hi);
+    }
+    min.resize(pixels, 0.0);
+    max.resize(pixels, 0.0);
+    
+    Ok(WaveformRange {
+        start,
+        end,
+        pixels,
+        frames_per_pixel,
+        min,
+        max,
+    })
+}
+
 // 解析时间字符串 (HH:MM:SS.ms 或 SS.ms)
 fn parse_time(time_str: &str) -> Result<f64, Box<dyn std::error::Error>> {
     let parts: Vec<&str> = time_str.split(':').collect();
@@ -54,6 +154,105 @@ fn get_video_duration(ffprobe_path: &str, video_path: &str) -> Result<f64, Box<d
     Ok(duration)
 }
 
+// 要分析的音频流 (多音轨文件)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AudioStreamSelector {
+    // 第一条音频流 (0:a:0)
+    #[default]
+    Default,
+    // 指定音频流，index 为音频流中的序号 (0:a:N)
+    Stream { index: u32 },
+    // 多条音频流混合后分析
+    Mix { indices: Vec<u32> },
+}
+
+impl AudioStreamSelector {
+    // 生成选择音频流所需的 ffmpeg 参数
+    fn ffmpeg_args(&self) -> Vec<String> {
+        match self {
+            // 显式映射第一条音频流：ffmpeg 自动选择的是声道最多的流，可能与探测声道数的流不一致
+            AudioStreamSelector::Default => vec!["-map".to_string(), "0:a:0".to_string()],
+            AudioStreamSelector::Stream { index } => vec!["-map".to_string(), format!("0:a:{}", index)],
+            AudioStreamSelector::Mix { indices } => {
+                let inputs: String = indices.iter().map(|i| format!("[0:a:{}]", i)).collect();
+                vec![
+                    "-filter_complex".to_string(),
+                    format!("{}amix=inputs={}:duration=longest:normalize=0[mix]", inputs, indices.len()),
+                    "-map".to_string(),
+                    "[mix]".to_string(),
+                ]
+            }
+        }
+    }
+    
+    // 缓存 ID：默认音频流沿用文件路径，其他选择附加后缀以免互相覆盖
+    fn cache_id(&self, video_path: &str) -> String {
+        match self {
+            AudioStreamSelector::Default => video_path.to_string(),
+            AudioStreamSelector::Stream { index } => format!("{}#a{}", video_path, index),
+            AudioStreamSelector::Mix { indices } => {
+                let list: Vec<String> = indices.iter().map(|i| i.to_string()).collect();
+                format!("{}#mix{}", video_path, list.join("+"))
+            }
+        }
+    }
+    
+    fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let AudioStreamSelector::Mix { indices } = self {
+            if indices.is_empty() {
+                return Err("混合音频流列表不能为空".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+// 提取时保留的声道数：按源音频的真实声道数逐声道存储，ChannelPolicy 才能单独判定环绕声中的任一声道
+// 代价是内存与磁盘缓存随声道数线性增长 (16kHz 下每声道每小时约 110MB，5.1 约 690MB)，
+// 内存部分受 AudioCache 的总量上限约束，超出时按 LRU 淘汰
+fn stored_channels(ffprobe_path: &str, video_path: &str, stream: &AudioStreamSelector) -> u32 {
+    get_audio_channels(ffprobe_path, video_path, stream).unwrap_or(1).max(1)
+}
+
+// 获取所选音频流的声道数，混合多条音频流时取其中最大的声道数
+fn get_audio_channels(
+    ffprobe_path: &str,
+    video_path: &str,
+    stream: &AudioStreamSelector,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    match stream {
+        AudioStreamSelector::Default => probe_stream_channels(ffprobe_path, video_path, 0),
+        AudioStreamSelector::Stream { index } => probe_stream_channels(ffprobe_path, video_path, *index),
+        AudioStreamSelector::Mix { indices } => {
+            let mut channels = 1;
+            for &index in indices {
+                channels = channels.max(probe_stream_channels(ffprobe_path, video_path, index)?);
+            }
+            Ok(channels)
+        }
+    }
+}
+
+// 获取第 N 条音频流的声道数
+fn probe_stream_channels(ffprobe_path: &str, video_path: &str, audio_index: u32) -> Result<u32, Box<dyn std::error::Error>> {
+    let output = Command::new(ffprobe_path)
+        .args(&["-v", "error"])
+        .args(&["-select_streams", &format!("a:{}", audio_index)])
+        .args(&["-show_entries", "stream=channels"])
+        .args(&["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(video_path)
+        .output()?;
+    
+    if !output.status.success() {
+        return Err("获取声道数失败".into());
+    }
+    
+    let channels_str = String::from_utf8(output.stdout)?;
+    let channels: u32 = channels_str.trim().parse()?;
+    Ok(channels.max(1))
+}
+
 // 音频数据
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AudioData {
@@ -66,8 +265,44 @@ pub struct AudioData {
     pub format: String,
     pub bit_depth: u32,
     pub cache_id: String,    // 用于后续分析的引用标识
+    // 使用外部音频时实际应用的偏移 (秒)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_offset: Option<f64>,
+}
+
+// 外部音频文件 (如单独录制的领夹麦)，用于驱动静音检测
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalAudio {
+    pub path: String,
+    #[serde(default)]
+    pub offset: AudioOffset,
+    // 外部音频文件中要使用的音频流，与视频的音频流选择相互独立
+    #[serde(default)]
+    pub stream: AudioStreamSelector,
+}
+
+// 外部音频相对视频的偏移：外部音频的 t 秒对应视频的 t + offset 秒
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AudioOffset {
+    Fixed { seconds: f64 },
+    // 与视频自带音频做互相关自动求偏移，搜索范围 ±max_offset 秒
+    #[serde(rename_all = "camelCase")]
+    Auto {
+        #[serde(default = "default_max_offset")]
+        max_offset: f64,
+    },
 }
 
+impl Default for AudioOffset {
+    fn default() -> Self {
+        AudioOffset::Fixed { seconds: 0.0 }
+    }
+}
+
+fn default_max_offset() -> f64 { 30.0 }
+
 // 静音片段
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 #[serde(rename_all = "camelCase")]
@@ -77,6 +312,56 @@ pub struct SilenceSegment {
     #[serde(default)]
     pub duration: f64,
     pub average_db: f64,
+    // 检测时实际使用的阈值 (dB)，自适应模式下为片段内的平均值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold_db: Option<f64>,
+    // 导出时对该静音段的处理方式，为空时使用导出选项中的默认值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub treatment: Option<SilenceTreatment>,
+}
+
+// 静音段的导出处理方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SilenceTreatment {
+    // 直接删除 (旧行为)
+    #[default]
+    Remove,
+    // 按 factor 倍速快进播放，保留画面变化
+    SpeedUp { factor: f64 },
+    // 停顿上限：超过 max_duration 秒的部分删除，只保留 max_duration 秒
+    #[serde(rename_all = "camelCase")]
+    Collapse {
+        max_duration: f64,
+        #[serde(default)]
+        keep: PauseKeep,
+    },
+}
+
+// 缩短停顿时保留的位置
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PauseKeep {
+    // 保留停顿中间一段
+    #[default]
+    Middle,
+    // 保留停顿首尾各一半，剪辑点落在停顿中间
+    Edges,
+}
+
+impl SilenceTreatment {
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match *self {
+            SilenceTreatment::Remove => Ok(()),
+            SilenceTreatment::SpeedUp { factor } if !(factor > 1.0 && factor <= 16.0) => {
+                Err("快进倍速必须大于 1 且不超过 16".into())
+            }
+            SilenceTreatment::Collapse { max_duration, .. } if !(max_duration >= 0.0 && max_duration.is_finite()) => {
+                Err("保留时长不能为负数".into())
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 impl SilenceSegment {
@@ -86,6 +371,89 @@ impl SilenceSegment {
             end_time,
             duration: end_time - start_time,
             average_db,
+            threshold_db: None,
+            treatment: None,
+        }
+    }
+}
+
+// 静音判定方式
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DetectionMode {
+    // 单阈值：窗口能量低于 threshold_db 即为静音
+    #[default]
+    Threshold,
+    // 双阈值 (迟滞)：低于 enter_db 进入静音，高于 exit_db 才退出，
+    // 状态需保持 hold_time 秒才生效
+    #[serde(rename_all = "camelCase")]
+    Hysteresis {
+        enter_db: f64,
+        exit_db: f64,
+        hold_time: f64,
+    },
+    // 自适应：在前后共 horizon 秒范围内跟踪底噪，阈值取底噪之上 offset_db
+    #[serde(rename_all = "camelCase")]
+    Adaptive {
+        horizon: f64,
+        offset_db: f64,
+    },
+    // 频谱 VAD：能量高于 threshold_db 之外，还要求语音频带 (300-3400Hz) 占比够高、
+    // 频谱不能太平坦、过零率不能太高，用于排除风扇嗡声、键盘声等非语音能量
+    #[serde(rename_all = "camelCase")]
+    Spectral {
+        #[serde(default = "default_min_band_ratio")]
+        min_band_ratio: f64,
+        #[serde(default = "default_max_flatness")]
+        max_flatness: f64,
+        #[serde(default = "default_max_zcr")]
+        max_zcr: f64,
+    },
+}
+
+fn default_min_band_ratio() -> f64 { 0.5 }
+fn default_max_flatness() -> f64 { 0.45 }
+fn default_max_zcr() -> f64 { 0.35 }
+
+// 多声道时参与判定的声道
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ChannelPolicy {
+    // 混成单声道后判定 (旧行为)
+    #[default]
+    Mixdown,
+    // 任一声道有声即为语音，即所有声道都静音才算静音
+    // 从静音角度命名的 "allSilent" 是同一策略，作为别名接受
+    #[serde(alias = "allSilent")]
+    AnyActive,
+    // 只看指定声道 (从 0 开始)
+    Channel { index: usize },
+}
+
+// 静音检测的附加参数（均以秒为单位）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DetectionOptions {
+    // 静音开始处保留的时长，保护上一句的尾音
+    pub pad_before: f64,
+    // 静音结束前保留的时长，保护下一句的起音
+    pub pad_after: f64,
+    // 最短保留语音长度，短于该值的语音孤岛会并入两侧静音
+    pub min_speech_duration: f64,
+    // 判定方式，默认单阈值
+    pub mode: DetectionMode,
+    // 多声道判定策略，默认混成单声道
+    pub channel_policy: ChannelPolicy,
+}
+
+impl Default for DetectionOptions {
+    fn default() -> Self {
+        Self {
+            pad_before: 0.0,
+            pad_after: 0.0,
+            min_speech_duration: 0.0,
+            mode: DetectionMode::Threshold,
+            channel_policy: ChannelPolicy::Mixdown,
         }
     }
 }
@@ -96,31 +464,65 @@ pub async fn extract_audio_streaming(
     ffprobe_path: &str,
     video_path: &str,
     sample_rate: u32,
-    window: &tauri::Window,
-    _threshold_db: f64,
+    stream: &AudioStreamSelector,
+    progress: &dyn ProgressSink,
+    threshold_db: f64,
+    min_silence_duration: f64,
+    options: &DetectionOptions,
 ) -> Result<AudioData, Box<dyn std::error::Error>> {
     use std::process::Stdio;
     use std::io::Read;
-    use tauri::Emitter;
 
+    stream.validate()?;
+    
+    // 磁盘缓存命中时直接恢复，跳过 ffmpeg
+    let cache_id = stream.cache_id(video_path);
+    let disk_key = disk_cache::cache_key(video_path, sample_rate, &stream.cache_id(""));
+    if let Some(audio_data) = restore_from_disk(disk_key.as_deref(), &cache_id, "s16le") {
+        // 数据已在内存中，直接完整检测一次作为临时剪辑建议
+        let silences = detect_silences(&cache_id, None, sample_rate, threshold_db, min_silence_duration, options)
+            .unwrap_or_default();
+        progress.report(ProgressEvent::WaveformDone(WaveformDone {
+            duration: audio_data.duration,
+            total_samples: (audio_data.duration * sample_rate as f64).round() as u64,
+            cache_id,
+            peaks: if audio_data.peaks.len() > 500000 { Vec::new() } else { audio_data.peaks.clone() },
+            silences,
+        }));
+        return Ok(audio_data);
+    }
+    
     let duration = get_video_duration(ffprobe_path, video_path).unwrap_or(0.0);
+    // 保留原始声道，交错的 s16le 按声道拆分存储
+    let channel_count = stored_channels(ffprobe_path, video_path, stream) as usize;
     
     // FFmpeg 命令：直接输出原始 PCM 数据到 stdout
     let mut child = Command::new(ffmpeg_path)
         .args(&["-i", video_path])
+        .args(stream.ffmpeg_args())
         .args(&["-vn"])
-        .args(&["-ac", "1"])
+        .args(&["-ac", &channel_count.to_string()])
         .args(&["-ar", &sample_rate.to_string()])
         .args(&["-f", "s16le"]) // 输出原始 16-bit 采样
         .arg("-")               // 输出到 stdout
+        .args(&["-loglevel", "error"])
         .stdout(Stdio::piped())
-        .stderr(Stdio::null())
+        .stderr(Stdio::piped())
         .spawn()?;
 
     let mut stdout = child.stdout.take().ok_or("无法打开 ffmpeg stdout")?;
+    // 在后台收集错误输出，避免管道写满阻塞 ffmpeg
+    let stderr_reader = child.stderr.take().map(|mut stderr| {
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            let _ = stderr.read_to_string(&mut text);
+            text
+        })
+    });
     
-    let mut all_samples = Vec::new();
+    let mut all_samples: Vec<Vec<i16>> = vec![Vec::new(); channel_count];
     let mut buffer = [0u8; 16384]; 
+    let frame_bytes = 2 * channel_count;
     let mut leftover: Vec<u8> = Vec::new();
     
     let mut peaks = Vec::new();
@@ -131,14 +533,18 @@ pub async fn extract_audio_streaming(
     
     let mut total_samples = 0;
     let mut final_peaks = Vec::new();
+    
+    // 边提取边检测，静音段确定后随波形一起发给前端
+    let mut detector = incremental::IncrementalDetector::new(sample_rate, threshold_db, min_silence_duration, options);
 
     loop {
         let n = match stdout.read(&mut buffer) {
             Ok(0) => break,
             Ok(n) => n,
             Err(e) => {
-                log::error!("读取音频流出错: {}", e);
-                break;
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("读取音频流出错: {}", e).into());
             }
         };
         
@@ -149,15 +555,20 @@ pub async fn extract_audio_streaming(
         leftover.clear();
 
         let mut i = 0;
-        while i + 1 < data.len() {
-            let s16 = i16::from_le_bytes([data[i], data[i+1]]);
-            let f32_sample = s16 as f32 / 32768.0;
-            
-            all_samples.push(f32_sample);
+        while i + frame_bytes <= data.len() {
+            // 一帧包含每个声道各一个采样，峰值取所有声道的最大值
+            let mut frame_sum = 0.0f32;
+            for (channel, samples) in all_samples.iter_mut().enumerate() {
+                let offset = i + channel * 2;
+                let s16 = i16::from_le_bytes([data[offset], data[offset + 1]]);
+                samples.push(s16);
+                current_peak = current_peak.max(s16.to_f32().abs());
+                frame_sum += s16.to_f32();
+            }
+            detector.push(frame_sum / channel_count as f32);
             total_samples += 1;
             
             // 计算峰值
-            current_peak = current_peak.max(f32_sample.abs());
             samples_in_peak += 1;
             
             if samples_in_peak >= peak_window {
@@ -166,52 +577,67 @@ pub async fn extract_audio_streaming(
                 
                 // 累计 100 个峰值 (约 2 秒) 发送一次，保证极致性能与响应
                 if peaks.len() >= 100 {
-                    let mut progress = if duration > 0.0 {
+                    let mut analyzed = if duration > 0.0 {
                         (total_samples as f64 / sample_rate as f64) / duration
                     } else {
                         0.0
                     };
                     
-                    if progress > 1.0 { progress = 1.0; }
+                    if analyzed > 1.0 { analyzed = 1.0; }
                     
-                    let _ = window.emit("audio-waveform-step", serde_json::json!({
-                        "peaks": peaks,
-                        "progress": progress,
+                    progress.report(ProgressEvent::WaveformStep(WaveformStep {
+                        peaks: std::mem::take(&mut peaks),
+                        progress: analyzed,
+                        silences: detector.take_ready(),
                     }));
-                    peaks = Vec::new();
                 }
                 
                 current_peak = 0.0;
                 samples_in_peak = 0;
             }
-            i += 2;
+            i += frame_bytes;
         }
 
-        // 保存未处理的单字节
+        // 保存未处理的不完整帧
         if i < data.len() {
             leftover.extend_from_slice(&data[i..]);
         }
     }
 
+    // ffmpeg 正常退出且确实产生了数据才写入缓存，避免把截断的音频当作完整结果
+    let status = child.wait()?;
+    let stderr_text = stderr_reader.and_then(|h| h.join().ok()).unwrap_or_default();
+    if !status.success() {
+        eprintln!("FFmpeg 音频提取失败: {}", stderr_text.trim());
+        return Err("FFmpeg 音频提取失败".into());
+    }
+    if total_samples == 0 {
+        return Err("未提取到音频数据".into());
+    }
+    
     let actual_duration = total_samples as f64 / sample_rate as f64;
+    let remaining_silences = detector.finish(total_samples);
     
-    // 关键步骤：存入缓存
-    let cache_id = video_path.to_string();
+    // 关键步骤：存入缓存 (同时写入磁盘缓存)
+    save_to_disk(disk_key.as_deref(), &all_samples, &final_peaks, sample_rate);
+    let cached = CachedAudio::new(all_samples, sample_rate);
     if let Ok(mut cache) = AUDIO_CACHE.lock() {
-        cache.insert(cache_id.clone(), all_samples);
+        cache.insert(cache_id.clone(), cached);
     }
 
     // 后端打印调试信息
-    println!("流式提取完成: {}, 时长: {:.2}s, 峰值数: {}", cache_id, actual_duration, final_peaks.len());
+    eprintln!("流式提取完成: {}, 时长: {:.2}s, 峰值数: {}", cache_id, actual_duration, final_peaks.len());
 
     // 最后发送一次完成状态
     // 我们允许最多 500,000 个峰值点通过 IPC 发送（约 2MB），
     // 即使是 5 小时的视频采集通常也在该范围内。
-    let _ = window.emit("audio-waveform-done", serde_json::json!({
-        "duration": actual_duration,
-        "totalSamples": total_samples,
-        "cache_id": cache_id.clone(),
-        "peaks": if final_peaks.len() > 500000 { Vec::<f32>::new() } else { final_peaks.clone() }
+    // 超出时不再附带峰值，前端通过 get_waveform_range 按缩放级别获取。
+    progress.report(ProgressEvent::WaveformDone(WaveformDone {
+        duration: actual_duration,
+        total_samples: total_samples as u64,
+        cache_id: cache_id.clone(),
+        peaks: if final_peaks.len() > 500000 { Vec::new() } else { final_peaks.clone() },
+        silences: remaining_silences,
     }));
 
     Ok(AudioData {
@@ -219,10 +645,11 @@ pub async fn extract_audio_streaming(
         peaks: final_peaks,
         sample_rate,
         duration: actual_duration,
-        channels: 1,
+        channels: channel_count as u32,
         format: "s16le".to_string(),
         cache_id,
         bit_depth: 16,
+        sync_offset: None,
     })
 }
 
@@ -232,28 +659,41 @@ pub async fn extract_audio_from_video(
     ffprobe_path: &str,
     video_path: &str,
     sample_rate: u32,
-    window: Option<&tauri::Window>,
+    stream: &AudioStreamSelector,
+    progress: &dyn ProgressSink,
 ) -> Result<AudioData, Box<dyn std::error::Error>> {
     use std::process::Stdio;
     use std::io::{BufRead, BufReader};
     use regex::Regex;
-    use tauri::Emitter;
+    
+    stream.validate()?;
+    
+    // 磁盘缓存命中时直接恢复，跳过 ffmpeg
+    let cache_id = stream.cache_id(video_path);
+    let disk_key = disk_cache::cache_key(video_path, sample_rate, &stream.cache_id(""));
+    if let Some(audio_data) = restore_from_disk(disk_key.as_deref(), &cache_id, "WAV") {
+        return Ok(audio_data);
+    }
     
     // 创建临时文件
     let temp_wav = NamedTempFile::new()?;
     let temp_wav_path = temp_wav.path().to_str().unwrap();
     
-    println!("提取音频到临时文件: {}", temp_wav_path);
+    eprintln!("提取音频到临时文件: {}", temp_wav_path);
     
     // 先获取视频时长
     let duration = get_video_duration(ffprobe_path, video_path)?;
-    println!("视频时长: {:.2}s", duration);
+    eprintln!("视频时长: {:.2}s", duration);
+    
+    // 保留原始声道
+    let channel_count = stored_channels(ffprobe_path, video_path, stream);
     
     // 使用 ffmpeg 提取音频为 WAV（带进度输出）
     let mut child = Command::new(ffmpeg_path)
         .args(&["-i", video_path])
+        .args(stream.ffmpeg_args())         // 选择音频流
         .args(&["-vn"])                     // 无视频
-        .args(&["-ac", "1"])                // 单声道
+        .args(&["-ac", &channel_count.to_string()]) // 声道数
         .args(&["-ar", &sample_rate.to_string()]) // 采样率
         .args(&["-acodec", "pcm_s16le"])    // 16-bit PCM
         .args(&["-f", "wav"])               // WAV 格式
@@ -294,12 +734,10 @@ pub async fn extract_audio_from_video(
                             };
                             
                             // 发送进度事件
-                            if let Some(win) = window {
-                                let _ = win.emit("analysis-progress", serde_json::json!({
-                                    "message": format!("正在提取音频... 速度: {:.2}x, 预计剩余: {}", speed, eta_text),
-                                    "percent": percent
-                                }));
-                            }
+                            progress.report(ProgressEvent::Analysis(StageProgress::new(
+                                percent,
+                                format!("正在提取音频... 速度: {:.2}x, 预计剩余: {}", speed, eta_text),
+                            ).with_eta(remaining_time)));
                         }
                     }
                 }
@@ -315,45 +753,161 @@ pub async fn extract_audio_from_video(
         return Err("FFmpeg 音频提取失败".into());
     }
     
-    println!("音频提取成功");
+    eprintln!("音频提取成功");
     
     // 获取音频信息
     let duration = get_audio_duration(ffprobe_path, temp_wav_path)?;
     let file_size = fs::metadata(temp_wav_path)?.len();
     
-    println!("音频时长: {:.2}s, 文件大小: {} bytes", duration, file_size);
+    eprintln!("音频时长: {:.2}s, 文件大小: {} bytes", duration, file_size);
     
     // 读取 WAV 文件数据
-    let samples = read_wav_file(temp_wav_path, sample_rate)?;
+    let channels = read_wav_file(temp_wav_path, sample_rate)?;
+    let channel_count = channels.len() as u32;
     
-    println!("读取了 {} 个样本", samples.len());
+    eprintln!("读取了 {} 个声道, 每声道 {} 个样本", channel_count, channels.first().map_or(0, |c| c.len()));
     
-    // 存入缓存
-    let cache_id = video_path.to_string();
+    let peaks = compute_peaks(&channels, sample_rate);
+    
+    // 存入缓存 (同时写入磁盘缓存)
+    save_to_disk(disk_key.as_deref(), &channels, &peaks, sample_rate);
+    let cached = CachedAudio::new(channels, sample_rate);
     if let Ok(mut cache) = AUDIO_CACHE.lock() {
-        cache.insert(cache_id.clone(), samples.clone());
+        cache.insert(cache_id.clone(), cached);
     }
 
-    // 计算快速预览峰值 (每 20ms 一个)
-    let peak_window = (sample_rate / 50) as usize;
-    let peaks: Vec<f32> = samples.chunks(peak_window)
-        .map(|chunk| chunk.iter().fold(0.0f32, |max, &s| max.max(s.abs())))
-        .collect();
-
     Ok(AudioData {
         samples: None,
         peaks,
         sample_rate,
         duration,
-        channels: 1,
+        channels: channel_count,
+        format: "WAV".to_string(),
+        cache_id,
+        bit_depth: 16,
+        sync_offset: None,
+    })
+}
+
+// 从磁盘缓存恢复分析数据，命中时同时放入内存缓存
+fn restore_from_disk(disk_key: Option<&str>, cache_id: &str, format: &str) -> Option<AudioData> {
+    let analysis = disk_cache::load(disk_key?)?;
+    let channel_count = analysis.channels.len() as u32;
+    let total_samples = analysis.channels.first().map_or(0, |c| c.len());
+    let duration = total_samples as f64 / analysis.sample_rate as f64;
+    eprintln!("💾 命中磁盘分析缓存: {} ({:.2}s, {} 声道)", cache_id, duration, channel_count);
+    
+    let cached = CachedAudio::new(analysis.channels, analysis.sample_rate);
+    if let Ok(mut cache) = AUDIO_CACHE.lock() {
+        cache.insert(cache_id.to_string(), cached);
+    }
+    
+    Some(AudioData {
+        samples: None,
+        peaks: analysis.peaks,
+        sample_rate: analysis.sample_rate,
+        duration,
+        channels: channel_count,
+        format: format.to_string(),
+        cache_id: cache_id.to_string(),
+        bit_depth: 16,
+        sync_offset: None,
+    })
+}
+
+// 写入磁盘缓存，失败只记录日志
+fn save_to_disk(disk_key: Option<&str>, channels: &[Vec<i16>], peaks: &[f32], sample_rate: u32) {
+    if let Some(key) = disk_key {
+        if let Err(e) = disk_cache::store(key, channels, peaks, sample_rate) {
+            log::warn!("写入磁盘分析缓存失败: {}", e);
+        }
+    }
+}
+
+// 计算快速预览峰值 (每 20ms 一个，取所有声道的最大值)
+fn compute_peaks(channels: &[Vec<i16>], sample_rate: u32) -> Vec<f32> {
+    let peak_window = (sample_rate / 50).max(1) as usize;
+    let mut peaks: Vec<f32> = Vec::new();
+    for samples in channels {
+        for (index, chunk) in samples.chunks(peak_window).enumerate() {
+            let peak = chunk.iter().fold(0.0f32, |max, &s| max.max(s.to_f32().abs()));
+            if index < peaks.len() {
+                peaks[index] = peaks[index].max(peak);
+            } else {
+                peaks.push(peak);
+            }
+        }
+    }
+    peaks
+}
+
+// 使用外部音频文件作为检测来源
+// 外部音频按偏移对齐到视频时间轴后存入缓存，后续检测结果直接是视频时间
+// video_stream 是视频中用于自动对齐的参考音轨，外部音频自身的音频流由 external.stream 指定
+pub async fn extract_external_audio(
+    ffmpeg_path: &str,
+    ffprobe_path: &str,
+    video_path: &str,
+    external: &ExternalAudio,
+    sample_rate: u32,
+    video_stream: &AudioStreamSelector,
+    progress: &dyn ProgressSink,
+) -> Result<AudioData, Box<dyn std::error::Error>> {
+    eprintln!("使用外部音频: {} -> {}", external.path, video_path);
+    let external_data = extract_audio_from_video(ffmpeg_path, ffprobe_path, &external.path, sample_rate, &external.stream, progress).await?;
+    
+    let offset = match &external.offset {
+        AudioOffset::Fixed { seconds } => *seconds,
+        AudioOffset::Auto { max_offset } => {
+            // 视频中的参考音轨 (scratch) 不在缓存中时先提取
+            let scratch_id = video_stream.cache_id(video_path);
+            let scratch_cached = AUDIO_CACHE.lock().map(|c| c.contains_key(&scratch_id)).unwrap_or(false);
+            if !scratch_cached {
+                extract_audio_from_video(ffmpeg_path, ffprobe_path, video_path, sample_rate, video_stream, &NoProgress).await?;
+            }
+            
+            let cache = AUDIO_CACHE.lock().unwrap();
+            let scratch = cache.peek(&scratch_id).ok_or("视频自带音频缓存不存在")?;
+            let external_audio = cache.peek(&external_data.cache_id).ok_or("外部音频缓存不存在")?;
+            if scratch.sample_rate != external_audio.sample_rate {
+                return Err("外部音频与视频音频采样率不一致".into());
+            }
+            sync::estimate_offset(&scratch.mixdown(), &external_audio.mixdown(), sample_rate, *max_offset)?
+        }
+    };
+    eprintln!("外部音频偏移: {:.3}s", offset);
+    
+    // 从缓存取出外部音频，对齐后以新的 ID 存回
+    let channels = AUDIO_CACHE.lock().unwrap()
+        .remove(&external_data.cache_id)
+        .map(|c| c.channels)
+        .ok_or("外部音频缓存不存在")?;
+    let channels = sync::shift_channels(channels, offset, sample_rate);
+    let channel_count = channels.len() as u32;
+    let total_samples = channels.first().map_or(0, |c| c.len());
+    let peaks = compute_peaks(&channels, sample_rate);
+    
+    let cache_id = format!("{}#ext:{}", video_path, external_data.cache_id);
+    let cached = CachedAudio::new(channels, sample_rate);
+    if let Ok(mut cache) = AUDIO_CACHE.lock() {
+        cache.insert(cache_id.clone(), cached);
+    }
+    
+    Ok(AudioData {
+        samples: None,
+        peaks,
+        sample_rate,
+        duration: total_samples as f64 / sample_rate as f64,
+        channels: channel_count,
         format: "WAV".to_string(),
         cache_id,
         bit_depth: 16,
+        sync_offset: Some(offset),
     })
 }
 
-// 读取 WAV 文件
-fn read_wav_file(wav_path: &str, expected_sample_rate: u32) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+// 读取 WAV 文件，按声道返回样本
+fn read_wav_file(wav_path: &str, expected_sample_rate: u32) -> Result<Vec<Vec<i16>>, Box<dyn std::error::Error>> {
     use std::io::{Read, Seek, SeekFrom};
     
     let mut file = fs::File::open(wav_path)?;
@@ -371,7 +925,7 @@ fn read_wav_file(wav_path: &str, expected_sample_rate: u32) -> Result<Vec<f32>,
     }
     
     let file_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
-    println!("RIFF 文件大小: {} bytes", file_size);
+    eprintln!("RIFF 文件大小: {} bytes", file_size);
     
     // 读取所有 chunks，找到 fmt 和 data
     let mut audio_format = 0u16;
@@ -394,7 +948,7 @@ fn read_wav_file(wav_path: &str, expected_sample_rate: u32) -> Result<Vec<f32>,
         let chunk_id = &chunk_header[0..4];
         let chunk_size = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
         
-        println!("找到 chunk: {:?}, 大小: {} bytes", 
+        eprintln!("找到 chunk: {:?}, 大小: {} bytes", 
                  std::str::from_utf8(chunk_id).unwrap_or("???"), chunk_size);
         
         if chunk_id == b"fmt " {
@@ -407,12 +961,12 @@ fn read_wav_file(wav_path: &str, expected_sample_rate: u32) -> Result<Vec<f32>,
             sample_rate = u32::from_le_bytes([fmt_data[4], fmt_data[5], fmt_data[6], fmt_data[7]]);
             bits_per_sample = u16::from_le_bytes([fmt_data[14], fmt_data[15]]);
             
-            println!("WAV 格式: 采样率={}Hz, 声道={}, 位深={}", sample_rate, num_channels, bits_per_sample);
+            eprintln!("WAV 格式: 采样率={}Hz, 声道={}, 位深={}", sample_rate, num_channels, bits_per_sample);
         } else if chunk_id == b"data" {
             // 找到 data chunk
             data_offset = current_pos + 8;
             data_size = chunk_size;
-            println!("找到 data chunk，偏移: {}, 大小: {} bytes", data_offset, data_size);
+            eprintln!("找到 data chunk，偏移: {}, 大小: {} bytes", data_offset, data_size);
         }
         
         // 移动到下一个 chunk (chunk 大小可能是奇数，需要对齐到偶数)
@@ -433,24 +987,24 @@ fn read_wav_file(wav_path: &str, expected_sample_rate: u32) -> Result<Vec<f32>,
     let mut pcm_data = vec![0u8; data_size as usize];
     file.read_exact(&mut pcm_data)?;
     
-    // 转换为 f32 样本
-    let num_samples = data_size as usize / (bits_per_sample as usize / 8) / (num_channels as usize);
-    let mut samples = Vec::with_capacity(num_samples);
+    // 按声道拆分 16-bit 样本
+    let num_channels = (num_channels as usize).max(1);
+    let num_samples = data_size as usize / (bits_per_sample as usize / 8) / num_channels;
+    let mut channels: Vec<Vec<i16>> = (0..num_channels).map(|_| Vec::with_capacity(num_samples)).collect();
     
-    // 16-bit PCM
+    // 16-bit PCM，交错存储
     for i in 0..num_samples {
-        let offset = i * num_channels as usize * 2;
-        let sample_i16 = i16::from_le_bytes([
-            pcm_data[offset],
-            pcm_data[offset + 1]
-        ]);
-        
-        // 归一化到 [-1.0, 1.0]
-        let sample_f32 = sample_i16 as f32 / 32768.0;
-        samples.push(sample_f32);
+        for (channel, samples) in channels.iter_mut().enumerate() {
+            let offset = (i * num_channels + channel) * 2;
+            let sample_i16 = i16::from_le_bytes([
+                pcm_data[offset],
+                pcm_data[offset + 1]
+            ]);
+            samples.push(sample_i16);
+        }
     }
     
-    Ok(samples)
+    Ok(channels)
 }
 
 // 获取音频时长
@@ -474,22 +1028,61 @@ pub fn detect_silences(
     sample_rate: u32,
     threshold_db: f64,
     min_silence_duration: f64,
+    options: &DetectionOptions,
 ) -> Result<Vec<SilenceSegment>, Box<dyn std::error::Error>> {
-    // 优先从缓存获取数据
-    let cache = AUDIO_CACHE.lock().unwrap();
-    let audio_data = if let Some(data) = cache.get(cache_id) {
-        println!("使用缓存数据进行分析: {}", cache_id);
-        data
+    let window_size = analysis_window_size(sample_rate);
+    if window_size == 0 {
+        return Err("采样率太低".into());
+    }
+    // 只有频谱模式需要原始样本，其余模式只看能量包络
+    let needs_samples = matches!(options.mode, DetectionMode::Spectral { .. });
+    
+    // 优先从缓存获取数据，按声道策略选出参与判定的声道
+    let mut cache = AUDIO_CACHE.lock().unwrap();
+    let (sources, total_samples): (Vec<DetectionSource>, usize) = if let Some(cached) = cache.get(cache_id) {
+        eprintln!("使用缓存数据进行分析: {} ({} 声道)", cache_id, cached.channels.len());
+        let total = cached.channels.iter().map(|c| c.len()).min().unwrap_or(0);
+        // 缓存的包络按提取时的采样率计算，窗口大小一致时直接复用
+        let envelope = if cached.envelope.window_size == window_size { Some(&cached.envelope) } else { None };
+        let sources = match options.channel_policy {
+            ChannelPolicy::Mixdown => vec![DetectionSource::new(
+                || cached.mixdown(),
+                envelope.map(|e| e.mixdown()),
+                window_size,
+                needs_samples,
+            )],
+            ChannelPolicy::AnyActive => cached.channels.iter()
+                .enumerate()
+                .map(|(i, c)| DetectionSource::new(
+                    || Cow::Borrowed(&c[..total]),
+                    envelope.map(|e| e.channels[i].as_slice()),
+                    window_size,
+                    needs_samples,
+                ))
+                .collect(),
+            ChannelPolicy::Channel { index } => {
+                let channel = cached.channels.get(index)
+                    .ok_or_else(|| format!("声道 {} 不存在，共 {} 个声道", index, cached.channels.len()))?;
+                vec![DetectionSource::new(
+                    || Cow::Borrowed(&channel[..total]),
+                    envelope.map(|e| e.channels[index].as_slice()),
+                    window_size,
+                    needs_samples,
+                )]
+            }
+        };
+        (sources, total)
     } else if let Some(fallback) = audio_data_fallback {
-        println!("由于缓存失效，使用传输的备选数据进行分析");
-        fallback
+        eprintln!("由于缓存失效，使用传输的备选数据进行分析");
+        let samples: Vec<i16> = fallback.iter().map(|&x| f32_to_i16(x)).collect();
+        (vec![DetectionSource::new(|| Cow::Owned(samples), None, window_size, needs_samples)], fallback.len())
     } else {
         return Err("无法获取音频数据：缓存已失效且未提供备选数据".into());
     };
 
     // 原有的检测逻辑（保持不变）
-    if audio_data.is_empty() {
-        println!("警告: 音频数据为空");
+    if total_samples == 0 {
+        eprintln!("警告: 音频数据为空");
         return Ok(Vec::new());
     }
     
@@ -497,121 +1090,313 @@ pub fn detect_silences(
         return Err("最小静音时长必须大于0".into());
     }
     
-    println!("============================================");
-    println!("开始静音检测 (ID: {})", cache_id);
-    println!("样本数: {}", audio_data.len());
-    println!("采样率: {} Hz", sample_rate);
-    println!("阈值: {} dB", threshold_db);
-    println!("最小静音时长: {} 秒", min_silence_duration);
+    if options.pad_before < 0.0 || options.pad_after < 0.0 || options.min_speech_duration < 0.0 {
+        return Err("边距与最短语音时长不能为负数".into());
+    }
+    
+    eprintln!("============================================");
+    eprintln!("开始静音检测 (ID: {})", cache_id);
+    eprintln!("样本数: {}", total_samples);
+    eprintln!("采样率: {} Hz", sample_rate);
+    eprintln!("阈值: {} dB", threshold_db);
+    eprintln!("最小静音时长: {} 秒", min_silence_duration);
+    eprintln!("边距: 前 {} 秒 / 后 {} 秒, 最短语音: {} 秒", options.pad_before, options.pad_after, options.min_speech_duration);
+    eprintln!("声道策略: {:?}", options.channel_policy);
     
-    // 转换为分贝阈值
-    let threshold_linear = db_to_linear(threshold_db);
     let min_silence_samples = (min_silence_duration * sample_rate as f64) as usize;
-    let window_size = (sample_rate as f64 * 0.02) as usize; // 20ms窗口
     
-    println!("线性阈值: {:.6}", threshold_linear);
-    println!("最小静音样本数: {}", min_silence_samples);
-    println!("窗口大小: {} 样本", window_size);
+    eprintln!("最小静音样本数: {}", min_silence_samples);
+    eprintln!("窗口大小: {} 样本", window_size);
     
-    if window_size == 0 {
-        return Err("采样率太低".into());
+    // 逐声道判定，再合并：只有所有参与的声道都静音，窗口才算静音
+    // 单个声道时直接借用 (缓存的) 能量包络，多个声道时才复制一份取最大值
+    let mut combined: Option<(Cow<[f64]>, WindowClassification)> = None;
+    for source in &sources {
+        let current = classify_windows(&source.energies, source.samples.as_deref(), sample_rate, window_size, threshold_db, &options.mode)?;
+        combined = Some(match combined {
+            None => (Cow::Borrowed(&*source.energies), current),
+            Some((mut energies, mut acc)) => {
+                let loudest = energies.to_mut();
+                for i in 0..acc.silent_flags.len() {
+                    acc.silent_flags[i] &= current.silent_flags[i];
+                    loudest[i] = loudest[i].max(source.energies[i]);
+                    acc.thresholds_db[i] = acc.thresholds_db[i].max(current.thresholds_db[i]);
+                }
+                (energies, acc)
+            }
+        });
     }
+    let (energies, classification) = combined.ok_or("没有可分析的声道")?;
     
-    let mut silences = Vec::new();
-    let mut in_silence = false;
-    let mut silence_start = 0;
-    let mut silence_energy_sum = 0.0;
-    let mut silence_windows = 0;
+    let silences = collect_silences(
+        &energies,
+        &classification.silent_flags,
+        &classification.thresholds_db,
+        window_size,
+        total_samples,
+        min_silence_samples,
+        sample_rate,
+    );
     
-    let mut total_windows = 0;
-    let mut silent_window_count = 0;
+    eprintln!("分析完成！合并片段中...");
     
-    // 滑动窗口检测
-    for (window_index, chunk) in audio_data.chunks(window_size).enumerate() {
-        let energy = calculate_rms(chunk);
-        total_windows += 1;
-        
-        // 每 5000 个窗口打印一次状态，减少日志
-        if window_index % 5000 == 0 {
-            let energy_db = linear_to_db(energy);
-            println!("分析进度 {}%: 能量={:.2} dB", (window_index * 100 / total_windows.max(1)), energy_db);
+    // 合并相邻静音段：间隔小于 100ms 或短于最短语音长度的语音孤岛一并吞掉
+    let merged = merge_close_silences(silences, merge_gap(options));
+    
+    // 向内收缩静音段，给语音留出边距
+    let total_duration = total_samples as f64 / sample_rate as f64;
+    let merged = apply_padding(merged, options.pad_before, options.pad_after, total_duration);
+    eprintln!("分析结果: 检测到 {} 个静音片段", merged.len());
+    eprintln!("============================================");
+    
+    Ok(merged)
+}
+
+// 参与判定的一个声道：逐窗口能量，频谱模式另需原始样本
+struct DetectionSource<'a> {
+    energies: Cow<'a, [f64]>,
+    samples: Option<Cow<'a, [i16]>>,
+}
+
+impl<'a> DetectionSource<'a> {
+    // 有缓存包络时不读取样本 (混合声道也不必重新混音)，否则由样本现算
+    fn new(
+        samples: impl FnOnce() -> Cow<'a, [i16]>,
+        envelope: Option<&'a [f64]>,
+        window_size: usize,
+        needs_samples: bool,
+    ) -> Self {
+        match envelope {
+            Some(energies) => Self {
+                energies: Cow::Borrowed(energies),
+                samples: if needs_samples { Some(samples()) } else { None },
+            },
+            None => {
+                let samples = samples();
+                Self {
+                    energies: Cow::Owned(window_energies(&samples, window_size)),
+                    samples: if needs_samples { Some(samples) } else { None },
+                }
+            }
         }
-        
-        if energy < threshold_linear {
-            silent_window_count += 1;
-            // 当前窗口是静音
-            if !in_silence {
-                // 开始新的静音段
-                in_silence = true;
-                silence_start = window_index * window_size;
-                silence_energy_sum = energy;
-                silence_windows = 1;
-            } else {
-                // 继续静音段
-                silence_energy_sum += energy;
-                silence_windows += 1;
+    }
+}
+
+// 单个声道的逐窗口判定结果
+struct WindowClassification {
+    silent_flags: Vec<bool>,
+    // 每个窗口实际使用的阈值 (dB)
+    thresholds_db: Vec<f64>,
+}
+
+// 按检测模式对一个声道逐窗口判定静音
+fn classify_windows(
+    energies: &[f64],
+    samples: Option<&[i16]>,
+    sample_rate: u32,
+    window_size: usize,
+    threshold_db: f64,
+    mode: &DetectionMode,
+) -> Result<WindowClassification, Box<dyn std::error::Error>> {
+    let threshold_linear = db_to_linear(threshold_db);
+    // 每个窗口的时长 (秒)，把以秒为单位的参数换算成窗口数
+    let window_seconds = window_size as f64 / sample_rate as f64;
+    
+    let (silent_flags, thresholds_db): (Vec<bool>, Vec<f64>) = match mode {
+        DetectionMode::Threshold => (
+            energies.iter().map(|&e| e < threshold_linear).collect(),
+            vec![threshold_db; energies.len()],
+        ),
+        DetectionMode::Hysteresis { enter_db, exit_db, hold_time } => {
+            if exit_db < enter_db {
+                return Err("退出静音阈值不能低于进入静音阈值".into());
             }
-        } else {
-            // 当前窗口不是静音
-            if in_silence {
-                // 静音段结束
-                in_silence = false;
-                let silence_end = window_index * window_size;
-                let silence_samples = silence_end - silence_start;
-                
-                if silence_samples >= min_silence_samples {
-                    let start_time = silence_start as f64 / sample_rate as f64;
-                    let end_time = silence_end as f64 / sample_rate as f64;
-                    let average_energy = silence_energy_sum / silence_windows as f64;
-                    let average_db = linear_to_db(average_energy);
-                    
-                    silences.push(SilenceSegment::new(
-                        start_time,
-                        end_time,
-                        average_db,
-                    ));
+            if *hold_time < 0.0 {
+                return Err("保持时间不能为负数".into());
+            }
+            let hold_windows = (hold_time / window_seconds).round() as usize;
+            eprintln!("双阈值模式: 进入 {} dB / 退出 {} dB, 保持 {} 个窗口", enter_db, exit_db, hold_windows);
+            (
+                hysteresis_flags(energies, db_to_linear(*enter_db), db_to_linear(*exit_db), hold_windows),
+                vec![*enter_db; energies.len()],
+            )
+        }
+        DetectionMode::Adaptive { horizon, offset_db } => {
+            if *horizon <= 0.0 {
+                return Err("底噪跟踪范围必须大于0".into());
+            }
+            let horizon_windows = ((horizon / window_seconds).round() as usize).max(1);
+            eprintln!("自适应模式: 跟踪范围 {} 秒 ({} 个窗口), 底噪之上 {} dB", horizon, horizon_windows, offset_db);
+            let thresholds = adaptive_thresholds(energies, horizon_windows, *offset_db);
+            let flags = energies.iter()
+                .zip(&thresholds)
+                .map(|(&e, &t)| linear_to_db(e) < t)
+                .collect();
+            (flags, thresholds)
+        }
+        DetectionMode::Spectral { min_band_ratio, max_flatness, max_zcr } => {
+            eprintln!("频谱 VAD 模式: 频带占比 >= {}, 平坦度 <= {}, 过零率 <= {}", min_band_ratio, max_flatness, max_zcr);
+            let params = vad::VadParams {
+                threshold_db,
+                min_band_ratio: *min_band_ratio,
+                max_flatness: *max_flatness,
+                max_zcr: *max_zcr,
+            };
+            let samples = samples.ok_or("频谱模式需要原始样本")?;
+            (
+                vad::silent_flags(samples, sample_rate, window_size, energies, &params),
+                vec![threshold_db; energies.len()],
+            )
+        }
+    };
+    
+    Ok(WindowClassification {
+        silent_flags,
+        thresholds_db,
+    })
+}
+
+// 双阈值 (迟滞) 判定：低于 enter 才进入静音，高于 exit 才退出静音
+// 状态翻转需要持续 hold_windows 个窗口，确认后回溯到候选起点，避免吃掉语音起音
+fn hysteresis_flags(energies: &[f64], enter: f64, exit: f64, hold_windows: usize) -> Vec<bool> {
+    let mut flags = Vec::with_capacity(energies.len());
+    let mut in_silence = false;
+    let mut candidate_start: Option<usize> = None;
+    
+    for (i, &energy) in energies.iter().enumerate() {
+        let wants_flip = if in_silence { energy > exit } else { energy < enter };
+        if wants_flip {
+            let start = *candidate_start.get_or_insert(i);
+            if i + 1 - start >= hold_windows.max(1) {
+                in_silence = !in_silence;
+                for flag in &mut flags[start..] {
+                    *flag = in_silence;
                 }
+                candidate_start = None;
             }
+        } else {
+            candidate_start = None;
         }
+        flags.push(in_silence);
     }
     
-    // 处理最后一段静音
-    if in_silence {
-        let silence_end = audio_data.len();
-        let silence_samples = silence_end - silence_start;
+    flags
+}
+
+// 滚动底噪跟踪：以每个窗口为中心、共 horizon_windows 个窗口内能量的 10% 分位作为底噪
+// 用 1dB 精度的滑动直方图求分位数，避免逐窗口排序
+fn adaptive_thresholds(energies: &[f64], horizon_windows: usize, offset_db: f64) -> Vec<f64> {
+    const MIN_DB: f64 = -100.0;
+    const BINS: usize = 101;
+    let to_bin = |energy: f64| (linear_to_db(energy).clamp(MIN_DB, 0.0) - MIN_DB) as usize;
+    
+    let half = horizon_windows / 2;
+    let mut histogram = [0usize; BINS];
+    let mut in_range = 0usize;
+    // 当前直方图覆盖 [lo, hi)
+    let mut lo = 0usize;
+    let mut hi = 0usize;
+    let mut thresholds = Vec::with_capacity(energies.len());
+    
+    for i in 0..energies.len() {
+        let target_hi = (i + half + 1).min(energies.len());
+        while hi < target_hi {
+            histogram[to_bin(energies[hi])] += 1;
+            in_range += 1;
+            hi += 1;
+        }
+        let target_lo = i.saturating_sub(half);
+        while lo < target_lo {
+            histogram[to_bin(energies[lo])] -= 1;
+            in_range -= 1;
+            lo += 1;
+        }
         
-        if silence_samples >= min_silence_samples {
-            let start_time = silence_start as f64 / sample_rate as f64;
-            let end_time = silence_end as f64 / sample_rate as f64;
-            let average_energy = silence_energy_sum / silence_windows as f64;
-            let average_db = linear_to_db(average_energy);
-            
-            silences.push(SilenceSegment::new(
-                start_time,
-                end_time,
-                average_db,
-            ));
+        let rank = (in_range as f64 * 0.1).ceil().max(1.0) as usize;
+        let mut seen = 0;
+        let mut floor_bin = 0;
+        for (bin, &count) in histogram.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                floor_bin = bin;
+                break;
+            }
         }
+        thresholds.push(MIN_DB + floor_bin as f64 + offset_db);
     }
     
-    println!("分析完成！合并片段中...");
+    thresholds
+}
+
+// 将逐窗口的静音标记整理成静音片段，丢弃短于最小静音长度的片段
+fn collect_silences(
+    energies: &[f64],
+    silent_flags: &[bool],
+    thresholds_db: &[f64],
+    window_size: usize,
+    total_samples: usize,
+    min_silence_samples: usize,
+    sample_rate: u32,
+) -> Vec<SilenceSegment> {
+    let mut silences = Vec::new();
+    let mut window_index = 0;
     
-    // 合并相邻静音段
-    let merged = merge_close_silences(silences, sample_rate);
-    println!("分析结果: 检测到 {} 个静音片段", merged.len());
-    println!("============================================");
+    while window_index < silent_flags.len() {
+        if !silent_flags[window_index] {
+            window_index += 1;
+            continue;
+        }
+        
+        let first = window_index;
+        while window_index < silent_flags.len() && silent_flags[window_index] {
+            window_index += 1;
+        }
+        
+        let silence_start = first * window_size;
+        let silence_end = (window_index * window_size).min(total_samples);
+        if silence_end - silence_start >= min_silence_samples {
+            let windows = (window_index - first) as f64;
+            let average_energy = energies[first..window_index].iter().sum::<f64>() / windows;
+            let average_threshold = thresholds_db[first..window_index].iter().sum::<f64>() / windows;
+            let mut silence = SilenceSegment::new(
+                silence_start as f64 / sample_rate as f64,
+                silence_end as f64 / sample_rate as f64,
+                linear_to_db(average_energy),
+            );
+            silence.threshold_db = Some(average_threshold);
+            silences.push(silence);
+        }
+    }
     
-    Ok(merged)
+    silences
+}
+
+// 相邻静音段间隔不超过该值 (秒) 时合并
+const MIN_MERGE_GAP: f64 = 0.1;
+
+// 实际合并间隔：短于最短语音长度的语音孤岛同样并入两侧静音
+fn merge_gap(options: &DetectionOptions) -> f64 {
+    options.min_speech_duration.max(MIN_MERGE_GAP)
+}
+
+// 分析窗口大小 (20ms)
+fn analysis_window_size(sample_rate: u32) -> usize {
+    (sample_rate as f64 * 0.02) as usize
+}
+
+// 逐窗口 RMS 能量包络
+fn window_energies<S: Sample>(samples: &[S], window_size: usize) -> Vec<f64> {
+    samples.chunks(window_size).map(calculate_rms).collect()
 }
 
 // 计算 RMS
-fn calculate_rms(samples: &[f32]) -> f64 {
+fn calculate_rms<S: Sample>(samples: &[S]) -> f64 {
     if samples.is_empty() {
         return 0.0;
     }
     
     let sum: f64 = samples.iter()
-        .map(|&x| (x as f64).powi(2))
+        .map(|&x| (x.to_f32() as f64).powi(2))
         .sum();
     
     (sum / samples.len() as f64).sqrt()
@@ -631,10 +1416,10 @@ fn db_to_linear(db: f64) -> f64 {
     10.0f64.powf(db / 20.0)
 }
 
-// 合并相邻静音
+// 合并相邻静音 (间隔不超过 gap_threshold 秒)
 fn merge_close_silences(
     mut silences: Vec<SilenceSegment>,
-    sample_rate: u32,
+    gap_threshold: f64,
 ) -> Vec<SilenceSegment> {
     if silences.len() < 2 {
         return silences;
@@ -643,7 +1428,6 @@ fn merge_close_silences(
     silences.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
     
     let mut merged = Vec::new();
-    let gap_threshold = 0.1; // 100ms间隔内合并
     
     let mut current = silences[0];
     
@@ -654,10 +1438,15 @@ fn merge_close_silences(
             let weight_current = current.duration / total_duration;
             let weight_next = next.duration / total_duration;
             let merged_db = current.average_db * weight_current + next.average_db * weight_next;
+            let merged_threshold = match (current.threshold_db, next.threshold_db) {
+                (Some(a), Some(b)) => Some(a * weight_current + b * weight_next),
+                (a, b) => a.or(b),
+            };
             
             current.end_time = next.end_time;
             current.duration = current.end_time - current.start_time;
             current.average_db = merged_db;
+            current.threshold_db = merged_threshold;
         } else {
             merged.push(current);
             current = *next;
@@ -668,6 +1457,30 @@ fn merge_close_silences(
     merged
 }
 
+// 收缩静音段两端，收缩后为空的片段直接丢弃
+// 位于文件开头/结尾的一侧没有相邻语音，不需要留边距
+fn apply_padding(
+    silences: Vec<SilenceSegment>,
+    pad_before: f64,
+    pad_after: f64,
+    total_duration: f64,
+) -> Vec<SilenceSegment> {
+    if pad_before <= 0.0 && pad_after <= 0.0 {
+        return silences;
+    }
+    
+    silences.into_iter()
+        .filter_map(|s| {
+            let start = if s.start_time > 0.0 { s.start_time + pad_before } else { s.start_time };
+            let end = if s.end_time < total_duration { s.end_time - pad_after } else { s.end_time };
+            if end - start <= 0.0 {
+                return None;
+            }
+            Some(SilenceSegment { threshold_db: s.threshold_db, ..SilenceSegment::new(start, end, s.average_db) })
+        })
+        .collect()
+}
+
 // 音频统计
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AudioStatistics {
@@ -683,8 +1496,8 @@ pub struct AudioStatistics {
 }
 
 // 计算音频统计
-pub fn calculate_statistics(
-    audio_data: &[f32],
+pub fn calculate_statistics<S: Sample>(
+    audio_data: &[S],
     sample_rate: u32,
 ) -> AudioStatistics {
     if audio_data.is_empty() {
@@ -707,7 +1520,7 @@ pub fn calculate_statistics(
     // 找到最小最大值
     let (min, max) = audio_data.iter()
         .fold((f32::MAX, f32::MIN), |(min, max), &x| {
-            (min.min(x), max.max(x))
+            (min.min(x.to_f32()), max.max(x.to_f32()))
         });
     
     // 计算 RMS 和峰值
@@ -745,4 +1558,259 @@ pub fn calculate_statistics(
         silence_ratio,
         detected_silences: 0,
     }
-}
\ No newline at end of file
+}
+
+// 电平分析结果，用于给前端预填阈值
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelAnalysis {
+    pub noise_floor_db: f64,
+    pub speech_level_db: f64,
+    // 20ms 窗口 RMS 的分贝直方图，从 histogram_min_db 开始每格 histogram_bin_db
+    pub histogram: Vec<u32>,
+    pub histogram_min_db: f64,
+    pub histogram_bin_db: f64,
+    pub suggested_threshold_db: f64,
+    pub suggested_min_silence_duration: f64,
+    pub statistics: AudioStatistics,
+}
+
+// 估算底噪与语音电平，给出建议阈值与最小静音时长
+pub fn analyze_levels(cache_id: &str) -> Result<LevelAnalysis, Box<dyn std::error::Error>> {
+    // 只在锁内复制出所需数据，排序与统计在释放锁之后进行，避免阻塞其它缓存访问
+    let (sample_rate, window_size, audio_data, energies) = {
+        let mut cache = AUDIO_CACHE.lock().unwrap();
+        let cached = cache.get(cache_id).ok_or("音频缓存不存在，请重新提取音频")?;
+        (
+            cached.sample_rate,
+            cached.envelope.window_size,
+            cached.mixdown().into_owned(),
+            cached.envelope.mixdown().to_vec(),
+        )
+    };
+    
+    if window_size == 0 {
+        return Err("采样率太低".into());
+    }
+    if audio_data.is_empty() {
+        return Err("音频数据为空".into());
+    }
+    
+    // 直接使用缓存的能量包络
+    let window_db: Vec<f64> = energies.iter()
+        .map(|&energy| linear_to_db(energy).clamp(-100.0, 0.0))
+        .collect();
+    
+    // 1dB 一格的直方图，覆盖 -100 ~ 0 dB
+    let histogram_min_db = -100.0;
+    let histogram_bin_db = 1.0;
+    let bin_count = 100;
+    let mut histogram = vec![0u32; bin_count];
+    for &db in &window_db {
+        let bin = ((db - histogram_min_db) / histogram_bin_db) as usize;
+        histogram[bin.min(bin_count - 1)] += 1;
+    }
+    
+    // 底噪取 10% 分位，语音电平取 95% 分位
+    let mut sorted = window_db.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let noise_floor_db = percentile(&sorted, 0.10);
+    let speech_level_db = percentile(&sorted, 0.95);
+    
+    // Otsu 分割直方图，再限制在底噪与语音电平之间
+    let otsu_db = histogram_min_db + (otsu_split(&histogram) as f64 + 1.0) * histogram_bin_db;
+    let suggested_threshold_db = if speech_level_db - noise_floor_db < 6.0 {
+        // 动态范围太小，无法可靠区分，保守地贴近底噪
+        noise_floor_db + 3.0
+    } else {
+        otsu_db.clamp(noise_floor_db + 3.0, speech_level_db - 3.0)
+    };
+    
+    // 统计建议阈值下的停顿长度，取较长停顿 (>150ms) 的下四分位作为最小静音时长
+    let window_secs = window_size as f64 / sample_rate as f64;
+    let mut pauses = Vec::new();
+    let mut run = 0usize;
+    for &db in window_db.iter().chain(std::iter::once(&0.0)) {
+        if db < suggested_threshold_db {
+            run += 1;
+        } else {
+            let secs = run as f64 * window_secs;
+            if secs >= 0.15 {
+                pauses.push(secs);
+            }
+            run = 0;
+        }
+    }
+    pauses.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let suggested_min_silence_duration = if pauses.is_empty() {
+        0.5
+    } else {
+        percentile(&pauses, 0.25).clamp(0.25, 1.5)
+    };
+    
+    let mut statistics = calculate_statistics(&audio_data, sample_rate);
+    statistics.detected_silences = pauses.iter().filter(|&&p| p >= suggested_min_silence_duration).count();
+    
+    eprintln!("电平分析 ({}): 底噪 {:.1} dB, 语音 {:.1} dB, 建议阈值 {:.1} dB, 建议最小静音 {:.2}s",
+        cache_id, noise_floor_db, speech_level_db, suggested_threshold_db, suggested_min_silence_duration);
+    
+    Ok(LevelAnalysis {
+        noise_floor_db,
+        speech_level_db,
+        histogram,
+        histogram_min_db,
+        histogram_bin_db,
+        suggested_threshold_db,
+        suggested_min_silence_duration,
+        statistics,
+    })
+}
+
+// 已排序数组的分位数
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index.min(sorted.len() - 1)]
+}
+
+// Otsu 法：返回使类间方差最大的分割格序号 (该格及以下为低电平类)
+fn otsu_split(histogram: &[u32]) -> usize {
+    let total: f64 = histogram.iter().map(|&c| c as f64).sum();
+    let weighted_total: f64 = histogram.iter().enumerate().map(|(i, &c)| i as f64 * c as f64).sum();
+    
+    let mut best_index = 0;
+    let mut best_variance = 0.0;
+    let mut weight_low = 0.0;
+    let mut sum_low = 0.0;
+    
+    for (i, &count) in histogram.iter().enumerate() {
+        weight_low += count as f64;
+        sum_low += i as f64 * count as f64;
+        let weight_high = total - weight_low;
+        if weight_low == 0.0 || weight_high == 0.0 {
+            continue;
+        }
+        let mean_low = sum_low / weight_low;
+        let mean_high = (weighted_total - sum_low) / weight_high;
+        let variance = weight_low * weight_high * (mean_low - mean_high).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_index = i;
+        }
+    }
+    
+    best_index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn silence(start: f64, end: f64) -> SilenceSegment {
+        SilenceSegment::new(start, end, -60.0)
+    }
+
+    fn spans(silences: &[SilenceSegment]) -> Vec<(f64, f64)> {
+        silences.iter().map(|s| ((s.start_time * 1000.0).round() / 1000.0, (s.end_time * 1000.0).round() / 1000.0)).collect()
+    }
+
+    #[test]
+    fn merge_joins_gaps_up_to_threshold() {
+        // 间隔正好等于阈值时合并，超过阈值时保留为两段
+        let merged = merge_close_silences(vec![silence(1.0, 2.0), silence(2.5, 3.0), silence(3.6, 4.0)], 0.5);
+        assert_eq!(spans(&merged), vec![(1.0, 3.0), (3.6, 4.0)]);
+        assert!((merged[0].duration - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn merge_sorts_and_weights_average_db() {
+        let mut loud = silence(0.0, 1.0);
+        loud.average_db = -40.0;
+        let mut quiet = silence(1.0, 4.0);
+        quiet.average_db = -80.0;
+        let merged = merge_close_silences(vec![quiet, loud], 0.1);
+        assert_eq!(spans(&merged), vec![(0.0, 4.0)]);
+        assert!((merged[0].average_db - -70.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn padding_shrinks_inner_sides_only() {
+        // 文件首尾一侧没有语音，不留边距
+        let padded = apply_padding(vec![silence(0.0, 2.0), silence(5.0, 8.0), silence(9.0, 10.0)], 0.2, 0.3, 10.0);
+        assert_eq!(spans(&padded), vec![(0.0, 1.7), (5.2, 7.7), (9.2, 10.0)]);
+    }
+
+    #[test]
+    fn padding_larger_than_silence_drops_it() {
+        // 两侧边距之和覆盖整段静音时，静音段被丢弃而不是与相邻语音重叠
+        let padded = apply_padding(vec![silence(3.0, 3.4), silence(5.0, 6.0)], 0.2, 0.2, 10.0);
+        assert_eq!(spans(&padded), vec![(5.2, 5.8)]);
+        assert!(padded.iter().all(|s| s.end_time > s.start_time));
+    }
+
+    #[test]
+    fn hysteresis_flips_only_after_hold_windows() {
+        let energies = [0.5, 0.05, 0.05, 0.5, 0.05, 0.05, 0.05, 0.15, 0.3, 0.3, 0.05, 0.3, 0.3, 0.3];
+        let flags = hysteresis_flags(&energies, 0.1, 0.2, 3);
+        // 两个低能量窗口不足以进入静音；进入后回溯到候选起点，
+        // 介于两阈值之间的窗口保持静音，高能量中断后重新计数
+        let expected = [false, false, false, false, true, true, true, true, true, true, true, false, false, false];
+        assert_eq!(flags, expected);
+    }
+
+    #[test]
+    fn hysteresis_zero_hold_flips_immediately() {
+        let flags = hysteresis_flags(&[0.5, 0.05, 0.15, 0.3], 0.1, 0.2, 0);
+        assert_eq!(flags, [false, true, true, false]);
+    }
+
+    #[test]
+    fn hysteresis_hold_time_uses_actual_window_length() {
+        // 40ms 窗口下 0.12 秒保持时间是 3 个窗口 (按 20ms 换算会是 6 个，永远进入不了静音)；
+        // 末尾单个高能量窗口不足以退出
+        let energies = [0.5, 0.001, 0.001, 0.001, 0.5];
+        let mode = DetectionMode::Hysteresis { enter_db: -40.0, exit_db: -30.0, hold_time: 0.12 };
+        let result = classify_windows(&energies, None, 16000, 640, -40.0, &mode).unwrap();
+        assert_eq!(result.silent_flags, [false, true, true, true, true]);
+    }
+
+    // 前 n 个窗口为 quiet_db，其后 m 个为 loud_db
+    fn two_level_energies(n: usize, quiet_db: f64, m: usize, loud_db: f64) -> Vec<f64> {
+        std::iter::repeat(db_to_linear(quiet_db)).take(n)
+            .chain(std::iter::repeat(db_to_linear(loud_db)).take(m))
+            .collect()
+    }
+
+    #[test]
+    fn adaptive_thresholds_follow_local_floor() {
+        // 底噪按 1dB 格向下取整：-60.5 → -61，-20.5 → -21
+        let energies = two_level_energies(10, -60.5, 10, -20.5);
+        let thresholds = adaptive_thresholds(&energies, 3, 6.0);
+        assert_eq!(thresholds[0], -55.0);
+        // 跟踪范围内仍有安静窗口时取安静窗口的底噪
+        assert_eq!(thresholds[10], -55.0);
+        assert_eq!(thresholds[11], -15.0);
+        assert_eq!(thresholds[19], -15.0);
+    }
+
+    #[test]
+    fn adaptive_thresholds_ignore_isolated_dips() {
+        // 20 个窗口取 10% 分位即第 2 低的值，单个安静窗口不会拉低底噪
+        let mut energies = two_level_energies(0, -60.5, 20, -20.5);
+        energies[7] = db_to_linear(-80.5);
+        let thresholds = adaptive_thresholds(&energies, 100, 0.0);
+        assert!(thresholds.iter().all(|&t| t == -21.0));
+    }
+
+    #[test]
+    fn adaptive_horizon_uses_actual_window_length() {
+        // 40ms 窗口下 0.12 秒是 3 个窗口，第 6 个窗口的范围内只有响亮窗口
+        let energies = two_level_energies(5, -60.5, 5, -20.5);
+        let mode = DetectionMode::Adaptive { horizon: 0.12, offset_db: 6.0 };
+        let result = classify_windows(&energies, None, 16000, 640, -40.0, &mode).unwrap();
+        assert_eq!(result.thresholds_db[4], -55.0);
+        assert_eq!(result.thresholds_db[6], -15.0);
+    }
+}
@@ -0,0 +1,162 @@
+// 外部音频与视频自带音频的对齐
+// 在 10ms 能量包络上做互相关 (FFT 加速)，求外部音频相对视频的时间偏移
+
+use super::vad::fft;
+use super::Sample;
+
+// 包络采样率 (每秒点数)
+const ENVELOPE_RATE: usize = 100;
+// 互相关峰值至少要达到的归一化相关系数，低于此值认为两段音频不匹配
+const MIN_CORRELATION: f64 = 0.3;
+
+// 估算偏移：外部音频的 t 秒对应视频的 t + offset 秒
+// reference 为视频自带音频，other 为外部音频，偏移搜索范围为 ±max_offset 秒
+pub fn estimate_offset<S: Sample>(
+    reference: &[S],
+    other: &[S],
+    sample_rate: u32,
+    max_offset: f64,
+) -> Result<f64, Box<dyn std::error::Error>> {
+    let hop = (sample_rate as usize / ENVELOPE_RATE).max(1);
+    let ref_env = envelope(reference, hop);
+    let other_env = envelope(other, hop);
+
+    if ref_env.len() < ENVELOPE_RATE || other_env.len() < ENVELOPE_RATE {
+        return Err("音频太短，无法自动对齐".into());
+    }
+
+    let ref_norm = ref_env.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let other_norm = other_env.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if ref_norm == 0.0 || other_norm == 0.0 {
+        return Err("音频没有有效内容，无法自动对齐".into());
+    }
+
+    // 补零到 2 的幂，避免循环相关的首尾混叠
+    let n = (ref_env.len() + other_env.len()).next_power_of_two();
+    let mut ref_re = vec![0.0; n];
+    let mut ref_im = vec![0.0; n];
+    let mut other_re = vec![0.0; n];
+    let mut other_im = vec![0.0; n];
+    ref_re[..ref_env.len()].copy_from_slice(&ref_env);
+    other_re[..other_env.len()].copy_from_slice(&other_env);
+    fft(&mut ref_re, &mut ref_im);
+    fft(&mut other_re, &mut other_im);
+
+    // R * conj(O)，再用共轭技巧做逆变换
+    let mut corr_re = vec![0.0; n];
+    let mut corr_im = vec![0.0; n];
+    for k in 0..n {
+        corr_re[k] = ref_re[k] * other_re[k] + ref_im[k] * other_im[k];
+        corr_im[k] = -(ref_im[k] * other_re[k] - ref_re[k] * other_im[k]);
+    }
+    fft(&mut corr_re, &mut corr_im);
+    // 此时 corr_re[lag] / n 即 Σ ref[i + lag] * other[i]，负的 lag 落在数组尾部
+
+    let max_lag = ((max_offset * ENVELOPE_RATE as f64) as usize).min(n / 2 - 1);
+    let mut best_lag = 0i64;
+    let mut best_value = f64::MIN;
+    for lag in 0..=max_lag {
+        for signed in [lag as i64, -(lag as i64)] {
+            let index = if signed >= 0 { signed as usize } else { n - (-signed) as usize };
+            let value = corr_re[index] / n as f64;
+            if value > best_value {
+                best_value = value;
+                best_lag = signed;
+            }
+        }
+    }
+
+    let correlation = best_value / (ref_norm * other_norm);
+    eprintln!("自动对齐: 偏移 {} 帧 ({:.2}s), 相关系数 {:.3}", best_lag, best_lag as f64 / ENVELOPE_RATE as f64, correlation);
+    if correlation < MIN_CORRELATION {
+        return Err(format!("自动对齐失败：外部音频与视频音频相关性太低 ({:.2})", correlation).into());
+    }
+
+    Ok(best_lag as f64 / ENVELOPE_RATE as f64)
+}
+
+// 按偏移移动各声道：正偏移在开头补静音，负偏移裁掉开头
+pub fn shift_channels<T: Copy + Default>(channels: Vec<Vec<T>>, offset: f64, sample_rate: u32) -> Vec<Vec<T>> {
+    let offset_samples = (offset * sample_rate as f64).round() as i64;
+    channels.into_iter()
+        .map(|samples| {
+            if offset_samples >= 0 {
+                let mut shifted = vec![T::default(); offset_samples as usize];
+                shifted.extend_from_slice(&samples);
+                shifted
+            } else {
+                let skip = ((-offset_samples) as usize).min(samples.len());
+                samples[skip..].to_vec()
+            }
+        })
+        .collect()
+}
+
+// 去均值的 RMS 包络
+fn envelope<S: Sample>(samples: &[S], hop: usize) -> Vec<f64> {
+    let env: Vec<f64> = samples.chunks(hop)
+        .map(|chunk| {
+            let sum: f64 = chunk.iter().map(|&x| (x.to_f32() as f64).powi(2)).sum();
+            (sum / chunk.len() as f64).sqrt()
+        })
+        .collect();
+    let mean = env.iter().sum::<f64>() / env.len().max(1) as f64;
+    env.into_iter().map(|x| x - mean).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 1000;
+
+    // 音量逐 50ms 随机变化的噪声，包络有明显起伏
+    fn bursts(seconds: usize, seed: u64) -> Vec<f32> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as f32 / (1u64 << 31) as f32
+        };
+        let mut samples = Vec::with_capacity(seconds * RATE as usize);
+        for _ in 0..seconds * RATE as usize / 50 {
+            let amplitude = next().powi(2);
+            for _ in 0..50 {
+                samples.push(amplitude * (next() * 2.0 - 1.0));
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn finds_positive_offset() {
+        let reference = bursts(20, 1);
+        // 外部音频晚开始 0.5s：它的 t 秒对应视频的 t + 0.5 秒
+        let other = reference[500..].to_vec();
+        let offset = estimate_offset(&reference, &other, RATE, 2.0).unwrap();
+        assert!((offset - 0.5).abs() < 0.011, "{}", offset);
+    }
+
+    #[test]
+    fn finds_negative_offset() {
+        let reference = bursts(20, 2);
+        let mut other = vec![0.0f32; 730];
+        other.extend_from_slice(&reference);
+        let offset = estimate_offset(&reference, &other, RATE, 2.0).unwrap();
+        assert!((offset + 0.73).abs() < 0.011, "{}", offset);
+    }
+
+    #[test]
+    fn unrelated_audio_is_rejected() {
+        let reference = bursts(60, 3);
+        let other = bursts(60, 4);
+        assert!(estimate_offset(&reference, &other, RATE, 2.0).is_err());
+    }
+
+    #[test]
+    fn shift_pads_or_trims_start() {
+        let channels = vec![vec![1, 2, 3], vec![4, 5, 6]];
+        assert_eq!(shift_channels(channels.clone(), 0.002, RATE), vec![vec![0, 0, 1, 2, 3], vec![0, 0, 4, 5, 6]]);
+        assert_eq!(shift_channels(channels.clone(), -0.002, RATE), vec![vec![3], vec![6]]);
+        assert_eq!(shift_channels(channels, -0.01, RATE), vec![Vec::<i32>::new(), Vec::new()]);
+    }
+}
@@ -0,0 +1,246 @@
+// 频谱语音活动检测 (VAD)
+// 纯 Rust 实现，不依赖任何模型：每个窗口计算语音频带能量占比、频谱平坦度和过零率
+
+use super::Sample;
+use std::f64::consts::PI;
+
+// 语音主要频带
+const SPEECH_BAND_LOW_HZ: f64 = 300.0;
+const SPEECH_BAND_HIGH_HZ: f64 = 3400.0;
+
+// 单个窗口的频谱特征
+#[derive(Debug, Clone, Copy)]
+pub struct FrameFeatures {
+    // 语音频带内能量 (dB, 相对满幅)
+    pub band_db: f64,
+    // 语音频带能量占总能量的比例
+    pub band_ratio: f64,
+    // 语音频带内的频谱平坦度，越接近 1 越像白噪声
+    pub flatness: f64,
+    // 每个采样点的过零率
+    pub zcr: f64,
+}
+
+// 判定参数
+#[derive(Debug, Clone, Copy)]
+pub struct VadParams {
+    pub threshold_db: f64,
+    pub min_band_ratio: f64,
+    pub max_flatness: f64,
+    pub max_zcr: f64,
+}
+
+// 逐窗口判定静音。能量低于阈值的窗口直接视为静音，不做 FFT
+pub fn silent_flags<S: Sample>(
+    samples: &[S],
+    sample_rate: u32,
+    window_size: usize,
+    energies: &[f64],
+    params: &VadParams,
+) -> Vec<bool> {
+    let threshold_linear = 10.0f64.powf(params.threshold_db / 20.0);
+    let mut analyzer = SpectrumAnalyzer::new(window_size, sample_rate);
+
+    samples.chunks(window_size)
+        .zip(energies)
+        .map(|(chunk, &energy)| {
+            if energy < threshold_linear {
+                return true;
+            }
+            let features = analyzer.analyze(chunk);
+            !(features.band_db >= params.threshold_db
+                && features.band_ratio >= params.min_band_ratio
+                && features.flatness <= params.max_flatness
+                && features.zcr <= params.max_zcr)
+        })
+        .collect()
+}
+
+// 复用 FFT 缓冲区与窗函数的分析器
+struct SpectrumAnalyzer {
+    fft_size: usize,
+    band_low: usize,
+    band_high: usize,
+    hann: Vec<f64>,
+    re: Vec<f64>,
+    im: Vec<f64>,
+}
+
+impl SpectrumAnalyzer {
+    fn new(window_size: usize, sample_rate: u32) -> Self {
+        let fft_size = window_size.next_power_of_two().max(2);
+        let bin_hz = sample_rate as f64 / fft_size as f64;
+        let nyquist_bin = fft_size / 2;
+        let band_low = ((SPEECH_BAND_LOW_HZ / bin_hz).ceil() as usize).clamp(1, nyquist_bin);
+        let band_high = ((SPEECH_BAND_HIGH_HZ / bin_hz).floor() as usize).clamp(band_low, nyquist_bin);
+        let hann = (0..window_size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / window_size.max(2) as f64).cos())
+            .collect();
+
+        Self {
+            fft_size,
+            band_low,
+            band_high,
+            hann,
+            re: vec![0.0; fft_size],
+            im: vec![0.0; fft_size],
+        }
+    }
+
+    fn analyze<S: Sample>(&mut self, chunk: &[S]) -> FrameFeatures {
+        // 过零率
+        let crossings = chunk.windows(2)
+            .filter(|w| (w[0].to_f32() >= 0.0) != (w[1].to_f32() >= 0.0))
+            .count();
+        let zcr = crossings as f64 / chunk.len().max(2) as f64;
+
+        // 加窗并补零
+        for i in 0..self.fft_size {
+            self.re[i] = if i < chunk.len() { chunk[i].to_f32() as f64 * self.hann[i] } else { 0.0 };
+            self.im[i] = 0.0;
+        }
+        fft(&mut self.re, &mut self.im);
+
+        // 只看正频率部分 (跳过直流)
+        let mut total_power = 0.0;
+        let mut band_power = 0.0;
+        let mut band_log_sum = 0.0;
+        for bin in 1..=self.fft_size / 2 {
+            let power = self.re[bin] * self.re[bin] + self.im[bin] * self.im[bin];
+            total_power += power;
+            if bin >= self.band_low && bin <= self.band_high {
+                band_power += power;
+                band_log_sum += (power + 1e-12).ln();
+            }
+        }
+
+        let band_bins = (self.band_high - self.band_low + 1) as f64;
+        let band_mean = band_power / band_bins;
+        let flatness = if band_mean > 0.0 {
+            (band_log_sum / band_bins).exp() / (band_mean + 1e-12)
+        } else {
+            1.0
+        };
+        let band_ratio = if total_power > 0.0 { band_power / total_power } else { 0.0 };
+
+        // 换算为与 RMS 可比的电平：Parseval 定理 + Hann 窗能量补偿 (约 0.375)
+        let window_energy = chunk.len() as f64 * 0.375;
+        let band_rms = (2.0 * band_power / (self.fft_size as f64 * window_energy.max(1.0))).sqrt();
+        let band_db = if band_rms > 0.0 { 20.0 * band_rms.log10() } else { -100.0 };
+
+        FrameFeatures {
+            band_db,
+            band_ratio,
+            flatness,
+            zcr,
+        }
+    }
+}
+
+// 原地迭代式基 2 FFT，长度必须是 2 的幂
+pub(super) fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+
+    // 位反转重排
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        let (w_im, w_re) = angle.sin_cos();
+        for start in (0..n).step_by(len) {
+            let mut cur_re = 1.0;
+            let mut cur_im = 0.0;
+            for k in 0..len / 2 {
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * cur_re - im[b] * cur_im;
+                let t_im = re[b] * cur_im + im[b] * cur_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+    const WINDOW: usize = 320;
+
+    fn params() -> VadParams {
+        VadParams { threshold_db: -40.0, min_band_ratio: 0.5, max_flatness: 0.45, max_zcr: 0.35 }
+    }
+
+    fn rms(samples: &[f32]) -> Vec<f64> {
+        samples.chunks(WINDOW)
+            .map(|c| (c.iter().map(|&x| (x as f64).powi(2)).sum::<f64>() / c.len() as f64).sqrt())
+            .collect()
+    }
+
+    #[test]
+    fn fft_puts_sinusoid_in_its_bin() {
+        let n = 64;
+        let mut re: Vec<f64> = (0..n).map(|i| (2.0 * PI * 5.0 * i as f64 / n as f64).cos()).collect();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        for bin in 0..n {
+            let expected = if bin == 5 || bin == n - 5 { n as f64 / 2.0 } else { 0.0 };
+            assert!((re[bin] - expected).abs() < 1e-9, "bin {}: {}", bin, re[bin]);
+            assert!(im[bin].abs() < 1e-9, "bin {}: {}", bin, im[bin]);
+        }
+    }
+
+    #[test]
+    fn speech_band_tone_is_voice() {
+        let samples: Vec<f32> = (0..RATE as usize)
+            .map(|i| 0.5 * (2.0 * PI * 1000.0 * i as f64 / RATE as f64).sin() as f32)
+            .collect();
+        let flags = silent_flags(&samples, RATE, WINDOW, &rms(&samples), &params());
+        assert_eq!(flags.len(), 50);
+        assert!(flags.iter().all(|&silent| !silent));
+    }
+
+    #[test]
+    fn white_noise_is_silence() {
+        let mut state = 7u64;
+        let samples: Vec<f32> = (0..RATE as usize)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                ((state >> 33) as f32 / (1u64 << 31) as f32 - 0.5) * 0.5
+            })
+            .collect();
+        let energies = rms(&samples);
+        // 能量远高于阈值，只能靠频谱特征判为静音
+        assert!(energies.iter().all(|&e| 20.0 * e.log10() > -20.0));
+        let flags = silent_flags(&samples, RATE, WINDOW, &energies, &params());
+        assert!(flags.iter().all(|&silent| silent));
+    }
+
+    #[test]
+    fn quiet_windows_skip_spectrum() {
+        let samples = vec![0.001f32; WINDOW * 2];
+        let flags = silent_flags(&samples, RATE, WINDOW, &rms(&samples), &params());
+        assert_eq!(flags, vec![true, true]);
+    }
+}
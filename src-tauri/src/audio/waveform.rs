@@ -0,0 +1,210 @@
+// 多分辨率波形峰值金字塔
+// 第 0 层每 BASE_BUCKET 帧记录一对 min/max (取所有声道)，往上每层合并 LEVEL_FACTOR 个桶，
+// 前端按当前缩放取所需分辨率，不再一次性传输整条峰值数组
+
+use serde::Serialize;
+
+// 第 0 层每个桶的帧数 (16kHz 下为 4ms)
+const BASE_BUCKET: usize = 64;
+// 相邻层之间的合并倍数
+const LEVEL_FACTOR: usize = 4;
+// 桶数少于此值时不再往上建层
+const MIN_LEVEL_BUCKETS: usize = 256;
+
+struct PeakLevel {
+    frames_per_bucket: usize,
+    min: Vec<i16>,
+    max: Vec<i16>,
+}
+
+pub struct PeakPyramid {
+    levels: Vec<PeakLevel>,
+}
+
+// 返回给前端的一段波形，每个像素一对 min/max (归一化到 [-1, 1])
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveformRange {
+    pub start: f64,
+    pub end: f64,
+    pub pixels: usize,
+    // 每个像素覆盖的采样帧数
+    pub frames_per_pixel: f64,
+    pub min: Vec<f32>,
+    pub max: Vec<f32>,
+}
+
+impl PeakPyramid {
+    pub fn build(channels: &[Vec<i16>]) -> Self {
+        let frames = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+        let bucket_count = (frames + BASE_BUCKET - 1) / BASE_BUCKET;
+        let mut base = PeakLevel {
+            frames_per_bucket: BASE_BUCKET,
+            min: Vec::with_capacity(bucket_count),
+            max: Vec::with_capacity(bucket_count),
+        };
+        for bucket in 0..bucket_count {
+            let range = bucket * BASE_BUCKET..((bucket + 1) * BASE_BUCKET).min(frames);
+            let (lo, hi) = min_max(channels, range);
+            base.min.push(lo);
+            base.max.push(hi);
+        }
+
+        let mut levels = vec![base];
+        loop {
+            let prev = levels.last().unwrap();
+            if prev.min.len() <= MIN_LEVEL_BUCKETS {
+                break;
+            }
+            let next = PeakLevel {
+                frames_per_bucket: prev.frames_per_bucket * LEVEL_FACTOR,
+                min: prev.min.chunks(LEVEL_FACTOR).map(|c| *c.iter().min().unwrap()).collect(),
+                max: prev.max.chunks(LEVEL_FACTOR).map(|c| *c.iter().max().unwrap()).collect(),
+            };
+            levels.push(next);
+        }
+
+        Self { levels }
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.levels.iter().map(|l| (l.min.len() + l.max.len()) * std::mem::size_of::<i16>()).sum()
+    }
+
+    // 取 [start_frame, end_frame) 区间按 pixels 个像素聚合的 min/max
+    // 从不超过每像素帧数的最粗层开始查，像素边缘不足一个桶的部分逐层细化到原始样本，结果精确
+    pub fn range(&self, channels: &[Vec<i16>], start_frame: usize, end_frame: usize, pixels: usize) -> (Vec<f32>, Vec<f32>) {
+        let frames_per_pixel = (end_frame - start_frame) as f64 / pixels as f64;
+        let depth = self.levels.iter()
+            .take_while(|l| l.frames_per_bucket as f64 <= frames_per_pixel)
+            .count();
+
+        let mut mins = Vec::with_capacity(pixels);
+        let mut maxs = Vec::with_capacity(pixels);
+        for p in 0..pixels {
+            let from = start_frame + (p as f64 * frames_per_pixel) as usize;
+            let to = (start_frame + ((p + 1) as f64 * frames_per_pixel) as usize).max(from + 1).min(end_frame);
+            let (lo, hi) = match self.query(channels, depth, from, to) {
+                (lo, hi) if lo > hi => (0, 0),
+                bounds => bounds,
+            };
+            mins.push(lo as f32 / 32768.0);
+            maxs.push(hi as f32 / 32768.0);
+        }
+        (mins, maxs)
+    }
+
+    // depth 为可用的层数 (0 表示只用原始样本)
+    fn query(&self, channels: &[Vec<i16>], depth: usize, from: usize, to: usize) -> (i16, i16) {
+        if from >= to {
+            return (i16::MAX, i16::MIN);
+        }
+        if depth == 0 {
+            return min_max(channels, from..to);
+        }
+
+        let level = &self.levels[depth - 1];
+        let size = level.frames_per_bucket;
+        let first = (from + size - 1) / size;
+        let last = (to / size).min(level.min.len());
+        if first >= last {
+            return self.query(channels, depth - 1, from, to);
+        }
+
+        let (head_lo, head_hi) = self.query(channels, depth - 1, from, first * size);
+        let (tail_lo, tail_hi) = self.query(channels, depth - 1, last * size, to);
+        let lo = level.min[first..last].iter().copied().fold(head_lo.min(tail_lo), i16::min);
+        let hi = level.max[first..last].iter().copied().fold(head_hi.max(tail_hi), i16::max);
+        (lo, hi)
+    }
+}
+
+// 所有声道在帧区间内的最小/最大值，区间为空时返回 (0, 0)
+fn min_max(channels: &[Vec<i16>], range: std::ops::Range<usize>) -> (i16, i16) {
+    let mut lo = i16::MAX;
+    let mut hi = i16::MIN;
+    for channel in channels {
+        if let Some(samples) = channel.get(range.clone()) {
+            for &s in samples {
+                lo = lo.min(s);
+                hi = hi.max(s);
+            }
+        }
+    }
+    if lo > hi { (0, 0) } else { (lo, hi) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 固定种子的伪随机样本，保证每个桶的极值位置各不相同
+    fn noise(frames: usize, seed: u32) -> Vec<i16> {
+        let mut state = seed;
+        (0..frames)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 16) as i16
+            })
+            .collect()
+    }
+
+    // 与 range 相同的像素划分，直接扫描原始样本
+    fn brute_force(channels: &[Vec<i16>], start: usize, end: usize, pixels: usize) -> (Vec<f32>, Vec<f32>) {
+        let frames_per_pixel = (end - start) as f64 / pixels as f64;
+        (0..pixels)
+            .map(|p| {
+                let from = start + (p as f64 * frames_per_pixel) as usize;
+                let to = (start + ((p + 1) as f64 * frames_per_pixel) as usize).max(from + 1).min(end);
+                let (lo, hi) = if from < to { min_max(channels, from..to) } else { (0, 0) };
+                (lo as f32 / 32768.0, hi as f32 / 32768.0)
+            })
+            .unzip()
+    }
+
+    #[test]
+    fn range_matches_raw_samples_with_partial_last_bucket() {
+        // 总帧数不是桶大小的整数倍，最后一个桶在每一层都不满
+        let frames = BASE_BUCKET * LEVEL_FACTOR * 300 + 37;
+        let channels = vec![noise(frames, 1)];
+        let pyramid = PeakPyramid::build(&channels);
+        assert!(pyramid.levels.len() >= 3);
+
+        for &(start, end, pixels) in &[
+            (0, frames, 100),
+            (0, frames, 7),
+            (13, frames, 333),
+            (frames - 500, frames, 3),
+            (1000, 1001, 1),
+        ] {
+            assert_eq!(pyramid.range(&channels, start, end, pixels), brute_force(&channels, start, end, pixels),
+                "start={} end={} pixels={}", start, end, pixels);
+        }
+    }
+
+    #[test]
+    fn range_takes_extremes_across_channels() {
+        let frames = BASE_BUCKET * 10 + 5;
+        let mut left = vec![0i16; frames];
+        let mut right = vec![0i16; frames];
+        left[frames - 1] = 20000;
+        right[3] = -30000;
+        let channels = vec![left, right];
+        let pyramid = PeakPyramid::build(&channels);
+
+        let (min, max) = pyramid.range(&channels, 0, frames, 1);
+        assert_eq!(min, vec![-30000.0 / 32768.0]);
+        assert_eq!(max, vec![20000.0 / 32768.0]);
+    }
+
+    #[test]
+    fn range_with_more_pixels_than_frames() {
+        // 像素比帧多时每个像素至少覆盖一帧，相邻像素会重复同一帧
+        let channels = vec![vec![100i16, -200, 300]];
+        let pyramid = PeakPyramid::build(&channels);
+        let (min, max) = pyramid.range(&channels, 0, 3, 6);
+        assert_eq!(min.len(), 6);
+        assert_eq!(pyramid.range(&channels, 0, 3, 6), brute_force(&channels, 0, 3, 6));
+        assert_eq!(max[5], 300.0 / 32768.0);
+    }
+}
@@ -0,0 +1,174 @@
+// src-tauri/src/bin/silence-cutter-cli.rs
+// 命令行版本：不启动界面，直接处理单个文件 (用于脚本与构建机)
+// 进度输出到 stderr，处理结果 (ProcessResult) 以 JSON 输出到 stdout
+
+use silence_cutter::utils::progress::{ProgressEvent, ProgressSink};
+use silence_cutter::video::{self, ExportOptions, ExportProfile, ProcessResult, ProcessSettings};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+const USAGE: &str = "用法: silence-cutter-cli <输入文件> <输出文件> [选项]
+
+选项:
+  --threshold <dB>          静音阈值，默认 -40
+  --min-duration <秒>       最短静音时长，默认 0.5
+  --padding <秒>            静音两端为语音保留的时长，默认 0
+  --profile <JSON|文件>     导出配置 (ExportProfile)，JSON 字符串或 JSON 文件路径
+  --ffmpeg <路径>           ffmpeg 路径，默认从 PATH 查找
+  --ffprobe <路径>          ffprobe 路径，默认从 PATH 查找
+  -h, --help                显示帮助";
+
+struct CliArgs {
+    input: String,
+    output: String,
+    settings: ProcessSettings,
+    ffmpeg_path: Option<String>,
+    ffprobe_path: Option<String>,
+}
+
+// 进度逐行输出到 stderr
+struct StderrProgress;
+
+impl ProgressSink for StderrProgress {
+    fn report(&self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::Analysis(p) | ProgressEvent::Export(p) => match p.eta {
+                Some(eta) if eta > 0.0 => eprintln!("[{:5.1}%] {} (剩余约 {:.0}s)", p.percent, p.message, eta),
+                _ => eprintln!("[{:5.1}%] {}", p.percent, p.message),
+            },
+            ProgressEvent::Batch(p) => eprintln!("[{:5.1}%] {}", p.percent, p.message),
+            // 波形预览只对界面有意义
+            ProgressEvent::WaveformStep(_) | ProgressEvent::WaveformDone(_) => {}
+        }
+    }
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<f64, String> {
+    let value = value.ok_or_else(|| format!("{} 缺少参数值", flag))?;
+    value.parse::<f64>().map_err(|_| format!("{} 的值无效: {}", flag, value))
+}
+
+// 导出配置可以直接写 JSON，也可以是 JSON 文件路径
+fn parse_profile(value: &str) -> Result<ExportProfile, String> {
+    let json = if value.trim_start().starts_with('{') {
+        value.to_string()
+    } else {
+        std::fs::read_to_string(value).map_err(|e| format!("读取导出配置失败 {}: {}", value, e))?
+    };
+    serde_json::from_str(&json).map_err(|e| format!("导出配置无效: {}", e))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<CliArgs>, String> {
+    let mut positional = Vec::new();
+    let mut settings = ProcessSettings::default();
+    let mut ffmpeg_path = None;
+    let mut ffprobe_path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--threshold" => settings.threshold_db = parse_number(&arg, args.next())?,
+            "--min-duration" => settings.min_silence_duration = parse_number(&arg, args.next())?,
+            "--padding" => {
+                let padding = parse_number(&arg, args.next())?;
+                if padding < 0.0 {
+                    return Err("--padding 不能为负".to_string());
+                }
+                settings.detection.pad_before = padding;
+                settings.detection.pad_after = padding;
+            }
+            "--profile" => {
+                let value = args.next().ok_or("--profile 缺少参数值")?;
+                settings.export = ExportOptions {
+                    profile: Some(parse_profile(&value)?),
+                    ..ExportOptions::default()
+                };
+            }
+            "--ffmpeg" => ffmpeg_path = Some(args.next().ok_or("--ffmpeg 缺少参数值")?),
+            "--ffprobe" => ffprobe_path = Some(args.next().ok_or("--ffprobe 缺少参数值")?),
+            _ if arg.starts_with("--") => return Err(format!("未知选项: {}", arg)),
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() != 2 {
+        return Err("需要指定输入文件和输出文件".to_string());
+    }
+    let output = positional.pop().unwrap_or_default();
+    let input = positional.pop().unwrap_or_default();
+    Ok(Some(CliArgs { input, output, settings, ffmpeg_path, ffprobe_path }))
+}
+
+// 未指定路径时从 PATH 查找
+fn find_tool(path: Option<String>, name: &str) -> Result<String, String> {
+    match path {
+        Some(path) => Ok(path),
+        None => which::which(name)
+            .map(|p| p.to_string_lossy().to_string())
+            .map_err(|_| format!("未找到 {}，请安装或通过 --{} 指定路径", name, name)),
+    }
+}
+
+fn print_result(result: &ProcessResult) {
+    match serde_json::to_string_pretty(result) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("序列化结果失败: {}", e),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            eprintln!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let tools = find_tool(args.ffmpeg_path, "ffmpeg")
+        .and_then(|ffmpeg| find_tool(args.ffprobe_path, "ffprobe").map(|ffprobe| (ffmpeg, ffprobe)));
+    let (ffmpeg_path, ffprobe_path) = match tools {
+        Ok(tools) => tools,
+        Err(e) => {
+            print_result(&ProcessResult::failed(&args.input, &args.output, e));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Ctrl+C 时停止 ffmpeg 并清理临时文件
+    let cancel = Arc::new(AtomicBool::new(false));
+    let cancel_on_signal = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("正在取消...");
+            cancel_on_signal.store(true, Ordering::SeqCst);
+        }
+    });
+
+    let result = video::process_file(
+        &ffmpeg_path,
+        &ffprobe_path,
+        &args.input,
+        &args.output,
+        &args.settings,
+        &StderrProgress,
+        cancel,
+    ).await;
+
+    match result {
+        Ok(result) => {
+            print_result(&result);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            print_result(&ProcessResult::failed(&args.input, &args.output, e.to_string()));
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,69 @@
+// src-tauri/src/commands/jobs.rs
+// 导出任务队列命令
+
+use crate::audio;
+use crate::jobs;
+use crate::video;
+use std::path::Path;
+
+// 提交导出任务，segments 为空时执行前重新检测静音
+#[tauri::command]
+pub async fn submit_job(
+    app_state: tauri::State<'_, crate::app::AppState>,
+    input_path: String,
+    output_path: Option<String>,
+    settings: Option<video::ProcessSettings>,
+    segments: Option<Vec<audio::SilenceSegment>>,
+) -> Result<jobs::Job, String> {
+    let ffmpeg_path = app_state.ffmpeg_path.as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .ok_or_else(|| "FFmpeg not found".to_string())?;
+
+    let settings = settings.unwrap_or_default();
+    settings.export.validate(&ffmpeg_path).await
+        .map_err(|e| format!("导出配置无效: {}", e))?;
+
+    let mut output_path = output_path.unwrap_or_else(|| super::video_processing::generate_output_path(&input_path));
+    if let Some(extension) = settings.export.output_extension() {
+        output_path = Path::new(&output_path)
+            .with_extension(extension)
+            .to_string_lossy()
+            .to_string();
+    }
+
+    jobs::submit(input_path, output_path, settings, segments)
+}
+
+// 查询全部任务
+#[tauri::command]
+pub async fn list_jobs() -> Result<Vec<jobs::Job>, String> {
+    Ok(jobs::list())
+}
+
+// 查询单个任务
+#[tauri::command]
+pub async fn get_job(job_id: String) -> Result<jobs::Job, String> {
+    jobs::get(&job_id).ok_or_else(|| format!("任务不存在: {}", job_id))
+}
+
+#[tauri::command]
+pub async fn cancel_job(job_id: String) -> Result<jobs::Job, String> {
+    jobs::cancel(&job_id)
+}
+
+// 暂停任务；执行中的任务会被停止，恢复后从头重新导出，已完成的部分不会保留
+#[tauri::command]
+pub async fn pause_job(job_id: String) -> Result<jobs::Job, String> {
+    jobs::pause(&job_id)
+}
+
+#[tauri::command]
+pub async fn resume_job(job_id: String) -> Result<jobs::Job, String> {
+    jobs::resume(&job_id)
+}
+
+// 清除已结束的任务记录，job_id 为空时清除全部
+#[tauri::command]
+pub async fn remove_finished_jobs(job_id: Option<String>) -> Result<usize, String> {
+    Ok(jobs::remove_finished(job_id.as_deref()))
+}
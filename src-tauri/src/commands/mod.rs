@@ -4,8 +4,12 @@
 mod system;
 mod video_processing;
 mod upload;
+mod jobs;
+mod watch;
 
 // 重新导出命令
 pub use system::*;
 pub use video_processing::*;
-pub use upload::*;
\ No newline at end of file
+pub use upload::*;
+pub use jobs::*;
+pub use watch::*;
\ No newline at end of file
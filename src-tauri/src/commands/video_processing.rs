@@ -4,9 +4,11 @@
 use crate::audio;
 use crate::video;
 use crate::app::ExportState;
+use crate::utils::progress::{ProgressEvent, ProgressSink, Stage, StageProgress, WaveformDone};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 
 // 视频处理请求
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,6 +20,15 @@ pub struct VideoProcessRequest {
     pub min_silence_duration: f64,
     pub sample_rate: Option<u32>,
     pub segments: Option<Vec<crate::audio::SilenceSegment>>,
+    #[serde(default)]
+    pub detection: audio::DetectionOptions,
+    #[serde(default)]
+    pub audio_stream: audio::AudioStreamSelector,
+    // 外部音频 (如领夹麦录音)，存在时用它检测静音，剪辑仍作用于视频
+    #[serde(default)]
+    pub external_audio: Option<audio::ExternalAudio>,
+    #[serde(default)]
+    pub export: video::ExportOptions,
 }
 
 // 视频处理响应
@@ -33,12 +44,18 @@ pub struct VideoProcessResponse {
     pub compression_ratio: f64,
     pub output_path: String,
     pub processing_time: f64,
+    // 关键帧对齐导出时各剪辑点的实际偏移
+    pub cut_adjustments: Vec<video::CutAdjustment>,
 }
 
+// 单个视频导出与批量处理在 ExportState 中登记的任务 ID
+const EXPORT_TASK_ID: &str = "export";
+const BATCH_TASK_ID: &str = "batch";
+
 // 取消导出命令
 #[tauri::command]
 pub async fn cancel_export(state: tauri::State<'_, ExportState>) -> Result<(), String> {
-    state.is_cancelled.store(true, Ordering::SeqCst);
+    state.cancel(EXPORT_TASK_ID);
     println!("🛑 收到取消信号，将尝试停止当前处理...");
     Ok(())
 }
@@ -63,6 +80,11 @@ pub async fn extract_audio(
     state: tauri::State<'_, crate::app::AppState>,
     path: String,
     sample_rate: Option<u32>,
+    stream: Option<audio::AudioStreamSelector>,
+    external_audio: Option<audio::ExternalAudio>,
+    threshold_db: Option<f64>,
+    min_silence_duration: Option<f64>,
+    detection: Option<audio::DetectionOptions>,
     window: tauri::Window,
 ) -> Result<audio::AudioData, String> {
     let ffmpeg_path = state.ffmpeg_path.as_ref()
@@ -77,15 +99,34 @@ pub async fn extract_audio(
     println!("开始流式提取音频: {}, 采样率: {}", path, sample_rate);
     
     // 发送初始进度事件
-    use tauri::Emitter;
-    let _ = window.emit("analysis-progress", serde_json::json!({
-        "stage": "extracting",
-        "message": "正在流式提取音频...",
-        "percent": 5
-    }));
+    window.report(ProgressEvent::Analysis(
+        StageProgress::new(5.0, "正在流式提取音频...").with_stage(Stage::Extracting),
+    ));
+    
+    let stream = stream.unwrap_or_default();
     
-    // 调用我们在 audio/mod.rs 中定义的流式处理函数
-    let result = audio::extract_audio_streaming(&ffmpeg_path, &ffprobe_path, &path, sample_rate, &window, -40.0).await;
+    // 外部音频需要先对齐再出波形，不走流式，完成后一次性发送波形
+    if let Some(external) = external_audio {
+        let result = audio::extract_external_audio(&ffmpeg_path, &ffprobe_path, &path, &external, sample_rate, &stream, &window).await;
+        let audio_data = result.map_err(|e| {
+            println!("提取外部音频失败: {}", e);
+            format!("提取外部音频失败: {}", e)
+        })?;
+        window.report(ProgressEvent::WaveformDone(WaveformDone {
+            duration: audio_data.duration,
+            total_samples: (audio_data.duration * sample_rate as f64) as u64,
+            cache_id: audio_data.cache_id.clone(),
+            peaks: audio_data.peaks.clone(),
+            silences: Vec::new(),
+        }));
+        return Ok(audio_data);
+    }
+    
+    // 调用我们在 audio/mod.rs 中定义的流式处理函数，同时用当前阈值给出临时静音段
+    let threshold_db = threshold_db.unwrap_or(-40.0);
+    let min_silence_duration = min_silence_duration.unwrap_or(0.5);
+    let detection = detection.unwrap_or_default();
+    let result = audio::extract_audio_streaming(&ffmpeg_path, &ffprobe_path, &path, sample_rate, &stream, &window, threshold_db, min_silence_duration, &detection).await;
     
     result.map_err(|e| {
         println!("提取音频失败: {}", e);
@@ -101,15 +142,13 @@ pub async fn detect_silences(
     sample_rate: u32,
     threshold_db: f64,
     min_silence_duration: f64,
+    options: Option<audio::DetectionOptions>,
     window: tauri::Window,
 ) -> Result<Vec<audio::SilenceSegment>, String> {
-    use tauri::Emitter;
     // 发送进度事件
-    let _ = window.emit("analysis-progress", serde_json::json!({
-        "stage": "detecting",
-        "message": "正在分析音频静音片段...",
-        "percent": 60
-    }));
+    window.report(ProgressEvent::Analysis(
+        StageProgress::new(60.0, "正在分析音频静音片段...").with_stage(Stage::Detecting),
+    ));
     
     audio::detect_silences(
         &cache_id,
@@ -117,10 +156,42 @@ pub async fn detect_silences(
         sample_rate,
         threshold_db,
         min_silence_duration,
+        &options.unwrap_or_default(),
     )
     .map_err(|e| format!("静音检测失败: {}", e))
 }
 
+// 估算底噪并给出建议阈值
+#[tauri::command]
+pub async fn analyze_audio_levels(cache_id: String) -> Result<audio::LevelAnalysis, String> {
+    audio::analyze_levels(&cache_id)
+        .map_err(|e| format!("电平分析失败: {}", e))
+}
+
+// 释放分析数据 (项目关闭时调用)，cache_id 为空时释放全部
+#[tauri::command]
+pub async fn release_audio_cache(cache_id: Option<String>) -> Result<usize, String> {
+    Ok(audio::release_cache(cache_id.as_deref()))
+}
+
+// 按时间区间取指定分辨率的波形
+#[tauri::command]
+pub async fn get_waveform_range(
+    cache_id: String,
+    start: f64,
+    end: f64,
+    pixels: usize,
+) -> Result<audio::WaveformRange, String> {
+    audio::waveform_range(&cache_id, start, end, pixels)
+        .map_err(|e| format!("获取波形失败: {}", e))
+}
+
+// 查询内存缓存占用
+#[tauri::command]
+pub async fn cache_stats() -> Result<audio::CacheStats, String> {
+    Ok(audio::cache_stats())
+}
+
 // 处理视频
 #[tauri::command]
 pub async fn process_video(
@@ -137,16 +208,38 @@ pub async fn process_video(
         .map(|p| p.to_string_lossy().to_string())
         .ok_or_else(|| "FFprobe not found".to_string())?;
 
-    // 重置取消标记
-    state.is_cancelled.store(false, Ordering::SeqCst);
-    
+    // 每次导出使用新的取消标记，取消只影响当前这次导出
+    let cancel_signal = state.begin(EXPORT_TASK_ID);
+    let result = export_video(&ffmpeg_path, &ffprobe_path, request, &window, cancel_signal.clone()).await;
+    state.finish(EXPORT_TASK_ID, &cancel_signal);
+    result
+}
+
+async fn export_video(
+    ffmpeg_path: &str,
+    ffprobe_path: &str,
+    request: VideoProcessRequest,
+    window: &tauri::Window,
+    cancel_signal: Arc<AtomicBool>,
+) -> Result<VideoProcessResponse, String> {
     let start_time = std::time::Instant::now();
 
+    // 先校验导出配置，避免提取完音频才发现编码器不可用
+    request.export.validate(ffmpeg_path).await
+        .map_err(|e| format!("导出配置无效: {}", e))?;
+
     // 生成输出路径
-    let output_path = match request.output_path {
+    let mut output_path = match request.output_path {
         Some(path) => path,
         None => generate_output_path(&request.input_path),
     };
+    // 输出扩展名与所选封装格式/音频格式保持一致
+    if let Some(extension) = request.export.output_extension() {
+        output_path = Path::new(&output_path)
+            .with_extension(extension)
+            .to_string_lossy()
+            .to_string();
+    }
     
     // 提取音频
     let sample_rate = request.sample_rate.unwrap_or(16000);
@@ -163,7 +256,10 @@ pub async fn process_video(
         segs
     } else {
         println!("未提供片段，开始从视频提取音频并检测...");
-        let result: Result<audio::AudioData, Box<dyn std::error::Error>> = audio::extract_audio_from_video(&ffmpeg_path, &ffprobe_path, &request.input_path, sample_rate, Some(&window)).await;
+        let result: Result<audio::AudioData, Box<dyn std::error::Error>> = match &request.external_audio {
+            Some(external) => audio::extract_external_audio(ffmpeg_path, ffprobe_path, &request.input_path, external, sample_rate, &request.audio_stream, window).await,
+            None => audio::extract_audio_from_video(ffmpeg_path, ffprobe_path, &request.input_path, sample_rate, &request.audio_stream, window).await,
+        };
         let audio_data = result.map_err(|e| {
             eprintln!("❌ 音频提取失败: {}", e);
             format!("音频提取失败: {}", e)
@@ -177,6 +273,7 @@ pub async fn process_video(
             audio_data.sample_rate,
             request.threshold_db,
             request.min_silence_duration,
+            &request.detection,
         )
         .map_err(|e| {
             eprintln!("❌ 静音检测失败: {}", e);
@@ -187,15 +284,14 @@ pub async fn process_video(
     println!("✅ 静音检测/获取完成: {} 个片段", silences.len());
     
     // 处理视频
-    let cancel_signal = state.is_cancelled.clone();
-    
     let video_result: Result<video::ProcessResult, Box<dyn std::error::Error>> = video::remove_silence_from_video(
-        &ffmpeg_path,
-        &ffprobe_path,
+        ffmpeg_path,
+        ffprobe_path,
         &request.input_path,
         &output_path,
         &silences,
-        Some(window),
+        &request.export,
+        window,
         cancel_signal,
     ).await;
     let result = video_result.map_err(|e| {
@@ -217,28 +313,68 @@ pub async fn process_video(
         compression_ratio: result.compression_ratio,
         output_path,
         processing_time,
+        cut_adjustments: result.cut_adjustments,
     })
 }
 
 // 批量处理
 #[tauri::command]
 pub async fn batch_process(
+    app_state: tauri::State<'_, crate::app::AppState>,
     input_paths: Vec<String>,
     output_dir: String,
     threshold_db: f64,
     min_silence_duration: f64,
+    sample_rate: Option<u32>,
+    detection: Option<audio::DetectionOptions>,
+    stream: Option<audio::AudioStreamSelector>,
+    export: Option<video::ExportOptions>,
+    window: tauri::Window,
+    state: tauri::State<'_, ExportState>,
 ) -> Result<Vec<video::ProcessResult>, String> {
+    let ffmpeg_path = app_state.ffmpeg_path.as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .ok_or_else(|| "FFmpeg not found".to_string())?;
+
+    let ffprobe_path = app_state.ffprobe_path.as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .ok_or_else(|| "FFprobe not found".to_string())?;
+
+    // 批量处理使用自己的取消标记，不影响同时进行的单个视频导出
+    let cancel_signal = state.begin(BATCH_TASK_ID);
+
+    let settings = video::ProcessSettings {
+        threshold_db,
+        min_silence_duration,
+        sample_rate: sample_rate.unwrap_or(16000),
+        detection: detection.unwrap_or_default(),
+        audio_stream: stream.unwrap_or_default(),
+        export: export.unwrap_or_default(),
+    };
+
     let result: Result<Vec<video::ProcessResult>, Box<dyn std::error::Error>> = video::batch_process_videos(
+        &ffmpeg_path,
+        &ffprobe_path,
         &input_paths,
         &output_dir,
-        threshold_db,
-        min_silence_duration,
+        &settings,
+        &window,
+        cancel_signal.clone(),
     ).await;
+    state.finish(BATCH_TASK_ID, &cancel_signal);
     result.map_err(|e| format!("批量处理失败: {}", e))
 }
 
+// 取消批量处理
+#[tauri::command]
+pub async fn cancel_batch(state: tauri::State<'_, ExportState>) -> Result<bool, String> {
+    let cancelled = state.cancel(BATCH_TASK_ID);
+    eprintln!("🛑 收到批量处理取消信号");
+    Ok(cancelled)
+}
+
 // 生成输出路径
-fn generate_output_path(input_path: &str) -> String {
+pub(crate) fn generate_output_path(input_path: &str) -> String {
     let path = Path::new(input_path);
     let parent = path.parent().unwrap_or(Path::new("."));
     let stem = path.file_stem()
@@ -0,0 +1,35 @@
+// src-tauri/src/commands/watch.rs
+// 监视文件夹命令
+
+use crate::watch;
+
+// 开始监视文件夹，新文件按 config.settings 自动加入导出队列
+#[tauri::command]
+pub async fn start_watch_folder(
+    app_state: tauri::State<'_, crate::app::AppState>,
+    config: watch::WatchConfig,
+) -> Result<watch::WatchFolder, String> {
+    let ffmpeg_path = app_state.ffmpeg_path.as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .ok_or_else(|| "FFmpeg not found".to_string())?;
+    config.settings.export.validate(&ffmpeg_path).await
+        .map_err(|e| format!("导出配置无效: {}", e))?;
+
+    watch::start(config)
+}
+
+#[tauri::command]
+pub async fn stop_watch_folder(watch_id: String) -> Result<(), String> {
+    watch::stop(&watch_id)
+}
+
+#[tauri::command]
+pub async fn list_watch_folders() -> Result<Vec<watch::WatchFolder>, String> {
+    Ok(watch::list())
+}
+
+// 查询处理台账，watch_id 为空时返回全部
+#[tauri::command]
+pub async fn get_watch_ledger(watch_id: Option<String>) -> Result<Vec<watch::LedgerEntry>, String> {
+    Ok(watch::ledger(watch_id.as_deref()))
+}
@@ -0,0 +1,522 @@
+// src-tauri/src/jobs/mod.rs
+// 后台导出任务队列
+// 每个任务有独立的 ID 与取消标记，按提交顺序逐个执行；
+// 队列保存在应用数据目录，重启后未完成的任务继续排队
+
+use crate::audio::SilenceSegment;
+use crate::utils::progress::{ProgressEvent, ProgressSink, StageProgress};
+use crate::video::{self, ProcessResult, ProcessSettings};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::Emitter;
+use tokio::sync::Notify;
+
+// 任务状态
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    // 暂停的任务不会被调度，恢复后重新排队 (执行中的任务暂停后从头开始)
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: String,
+    pub input_path: String,
+    pub output_path: String,
+    pub settings: ProcessSettings,
+    // 前端已确认的静音片段，为空时导出前重新检测
+    #[serde(default)]
+    pub segments: Option<Vec<SilenceSegment>>,
+    pub status: JobStatus,
+    pub created_at: String,
+    #[serde(default)]
+    pub started_at: Option<String>,
+    #[serde(default)]
+    pub finished_at: Option<String>,
+    #[serde(default)]
+    pub result: Option<ProcessResult>,
+    #[serde(default)]
+    pub error: Option<String>,
+    // 执行中的进度，只在内存中更新，重启后不恢复
+    #[serde(default, skip_deserializing)]
+    pub progress: Option<StageProgress>,
+}
+
+// 任务进度事件 (job-progress)
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct JobProgress {
+    job_id: String,
+    #[serde(flatten)]
+    progress: StageProgress,
+}
+
+// 把导出进度记录到任务上，并转发为 job-progress 事件
+struct JobProgressSink {
+    id: String,
+}
+
+impl ProgressSink for JobProgressSink {
+    fn report(&self, event: ProgressEvent) {
+        let progress = match event {
+            ProgressEvent::Analysis(progress) | ProgressEvent::Export(progress) => progress,
+            _ => return,
+        };
+        let mut queue = QUEUE.lock().unwrap();
+        if let Ok(job) = queue.find_mut(&self.id) {
+            job.progress = Some(progress.clone());
+        }
+        if let Some(app) = &queue.app {
+            let _ = app.emit("job-progress", JobProgress { job_id: self.id.clone(), progress });
+        }
+    }
+}
+
+// 正在执行的任务
+struct RunningJob {
+    id: String,
+    cancel: Arc<AtomicBool>,
+    // 取消标记由暂停触发时，任务结束后回到暂停状态而不是已取消
+    pause_requested: bool,
+}
+
+#[derive(Default)]
+struct JobQueue {
+    jobs: Vec<Job>,
+    running: Option<RunningJob>,
+    store_path: Option<PathBuf>,
+    app: Option<tauri::AppHandle>,
+    ffmpeg_path: Option<String>,
+    ffprobe_path: Option<String>,
+}
+
+static QUEUE: Lazy<Mutex<JobQueue>> = Lazy::new(|| Mutex::new(JobQueue::default()));
+// 有新任务可执行时唤醒后台线程
+static WAKE: Lazy<Notify> = Lazy::new(Notify::new);
+
+impl JobQueue {
+    fn find_mut(&mut self, id: &str) -> Result<&mut Job, String> {
+        self.jobs.iter_mut().find(|j| j.id == id).ok_or_else(|| format!("任务不存在: {}", id))
+    }
+
+    // 写入磁盘：先写临时文件再重命名
+    fn save(&self) {
+        let path = match &self.store_path {
+            Some(p) => p,
+            None => return,
+        };
+        let temp_path = path.with_extension("tmp");
+        let result = serde_json::to_vec_pretty(&self.jobs)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| fs::write(&temp_path, bytes).map_err(|e| e.to_string()))
+            .and_then(|_| fs::rename(&temp_path, path).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("保存任务队列失败: {}", e);
+        }
+    }
+
+    fn notify(&self, job: &Job) {
+        if let Some(app) = &self.app {
+            let _ = app.emit("job-updated", job.clone());
+        }
+    }
+
+    // 载入上次保存的队列：上次退出时正在执行的任务重新排队
+    fn restore(&mut self, jobs: Vec<Job>) {
+        self.jobs = jobs;
+        for job in self.jobs.iter_mut().filter(|j| j.status == JobStatus::Running) {
+            job.status = JobStatus::Queued;
+            job.started_at = None;
+        }
+    }
+
+    // 停止任务，返回任务及其状态是否已经改变
+    // 执行中的任务只设置取消标记，状态由 finish 在任务真正结束后更新
+    fn stop(&mut self, id: &str, pause: bool) -> Result<(Job, bool), String> {
+        if let Some(running) = self.running.as_mut().filter(|r| r.id == id) {
+            running.pause_requested = pause;
+            running.cancel.store(true, Ordering::SeqCst);
+            return self.find_mut(id).map(|j| (j.clone(), false));
+        }
+
+        let job = self.find_mut(id)?;
+        match job.status {
+            JobStatus::Queued | JobStatus::Paused => {
+                job.status = if pause { JobStatus::Paused } else { JobStatus::Cancelled };
+                if !pause {
+                    job.finished_at = Some(chrono::Local::now().to_rfc3339());
+                }
+            }
+            _ => return Err("任务已结束".to_string()),
+        }
+        Ok((job.clone(), true))
+    }
+
+    // 暂停的任务重新排队
+    fn resume(&mut self, id: &str) -> Result<Job, String> {
+        let job = self.find_mut(id)?;
+        if job.status != JobStatus::Paused {
+            return Err("只能恢复已暂停的任务".to_string());
+        }
+        job.status = JobStatus::Queued;
+        Ok(job.clone())
+    }
+
+    // 取第一个排队中的任务标记为执行中，返回任务与它的取消标记
+    fn start_next(&mut self) -> Option<(Job, Arc<AtomicBool>)> {
+        let job = self.jobs.iter_mut().find(|j| j.status == JobStatus::Queued)?;
+        job.status = JobStatus::Running;
+        job.started_at = Some(chrono::Local::now().to_rfc3339());
+        job.error = None;
+        job.progress = None;
+        let job = job.clone();
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.running = Some(RunningJob { id: job.id.clone(), cancel: cancel.clone(), pause_requested: false });
+        Some((job, cancel))
+    }
+
+    // 记录执行结果；因暂停而停止的任务回到暂停状态，恢复后从头执行
+    fn finish(&mut self, id: &str, result: Result<ProcessResult, String>) -> Option<Job> {
+        let pause_requested = self.running.take().is_some_and(|r| r.pause_requested);
+        let entry = self.find_mut(id).ok()?;
+        match result {
+            Ok(result) => {
+                entry.status = JobStatus::Completed;
+                entry.result = Some(result);
+            }
+            Err(e) if e == "EXPORT_CANCELLED" => {
+                entry.status = if pause_requested { JobStatus::Paused } else { JobStatus::Cancelled };
+            }
+            Err(e) => {
+                entry.status = JobStatus::Failed;
+                entry.error = Some(e);
+            }
+        }
+        entry.progress = None;
+        if entry.status.is_finished() {
+            entry.finished_at = Some(chrono::Local::now().to_rfc3339());
+        } else {
+            entry.started_at = None;
+        }
+        Some(entry.clone())
+    }
+
+    // 删除已结束的任务记录，id 为空时删除全部已结束的任务
+    fn remove_finished(&mut self, id: Option<&str>) -> usize {
+        let before = self.jobs.len();
+        self.jobs.retain(|j| !(j.status.is_finished() && (id.is_none() || id == Some(j.id.as_str()))));
+        before - self.jobs.len()
+    }
+}
+
+// 应用启动时调用：读取上次保存的队列并启动后台执行，store_path 为空时队列只保存在内存中
+pub fn init(app: tauri::AppHandle, store_path: Option<PathBuf>, ffmpeg_path: Option<String>, ffprobe_path: Option<String>) {
+    if let Some(parent) = store_path.as_ref().and_then(|p| p.parent()) {
+        let _ = fs::create_dir_all(parent);
+    }
+    let jobs: Vec<Job> = store_path.as_ref()
+        .and_then(|p| fs::read(p).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    {
+        let mut queue = QUEUE.lock().unwrap();
+        queue.restore(jobs);
+        let pending = queue.jobs.iter().filter(|j| j.status == JobStatus::Queued).count();
+        log::info!("任务队列: {} 个任务, {} 个待执行", queue.jobs.len(), pending);
+        queue.store_path = store_path;
+        queue.app = Some(app);
+        queue.ffmpeg_path = ffmpeg_path;
+        queue.ffprobe_path = ffprobe_path;
+        queue.save();
+    }
+
+    tauri::async_runtime::spawn(run_worker());
+}
+
+// 提交任务
+pub fn submit(
+    input_path: String,
+    output_path: String,
+    settings: ProcessSettings,
+    segments: Option<Vec<SilenceSegment>>,
+) -> Result<Job, String> {
+    if !std::path::Path::new(&input_path).exists() {
+        return Err(format!("输入文件不存在: {}", input_path));
+    }
+
+    let now = chrono::Local::now();
+    let job = Job {
+        id: format!("job_{}_{:08x}", now.timestamp_millis(), rand::random::<u32>()),
+        input_path,
+        output_path,
+        settings,
+        segments,
+        status: JobStatus::Queued,
+        created_at: now.to_rfc3339(),
+        started_at: None,
+        finished_at: None,
+        result: None,
+        error: None,
+        progress: None,
+    };
+
+    let mut queue = QUEUE.lock().unwrap();
+    queue.jobs.push(job.clone());
+    queue.save();
+    queue.notify(&job);
+    drop(queue);
+
+    WAKE.notify_one();
+    Ok(job)
+}
+
+pub fn list() -> Vec<Job> {
+    QUEUE.lock().unwrap().jobs.clone()
+}
+
+pub fn get(id: &str) -> Option<Job> {
+    QUEUE.lock().unwrap().jobs.iter().find(|j| j.id == id).cloned()
+}
+
+// 取消任务：排队/暂停中的直接取消，执行中的停止 ffmpeg 后由后台线程标记
+pub fn cancel(id: &str) -> Result<Job, String> {
+    stop(id, false)
+}
+
+// 暂停任务：排队中的不再调度，执行中的停止后回到暂停状态
+// 执行中的导出无法从中间继续，恢复后从头重新导出
+pub fn pause(id: &str) -> Result<Job, String> {
+    stop(id, true)
+}
+
+fn stop(id: &str, pause: bool) -> Result<Job, String> {
+    let mut queue = QUEUE.lock().unwrap();
+    let (job, changed) = queue.stop(id, pause)?;
+    if changed {
+        queue.save();
+        queue.notify(&job);
+    } else {
+        log::info!("正在停止任务 {}", id);
+    }
+    Ok(job)
+}
+
+// 恢复暂停的任务 (重新排队)
+pub fn resume(id: &str) -> Result<Job, String> {
+    let mut queue = QUEUE.lock().unwrap();
+    let job = queue.resume(id)?;
+    queue.save();
+    queue.notify(&job);
+    drop(queue);
+
+    WAKE.notify_one();
+    Ok(job)
+}
+
+// 删除已结束的任务记录，id 为空时清除全部已结束的任务
+pub fn remove_finished(id: Option<&str>) -> usize {
+    let mut queue = QUEUE.lock().unwrap();
+    let removed = queue.remove_finished(id);
+    if removed > 0 {
+        queue.save();
+    }
+    removed
+}
+
+// 后台线程：每次取第一个排队中的任务执行
+async fn run_worker() {
+    loop {
+        let next = {
+            let mut queue = QUEUE.lock().unwrap();
+            let tools = (queue.ffmpeg_path.clone(), queue.ffprobe_path.clone());
+            let next = queue.start_next();
+            if let Some((job, _)) = &next {
+                queue.save();
+                queue.notify(job);
+            }
+            next.map(|(job, cancel)| (job, cancel, tools))
+        };
+
+        let (job, cancel, tools) = match next {
+            Some(next) => next,
+            None => {
+                WAKE.notified().await;
+                continue;
+            }
+        };
+
+        log::info!("开始执行任务 {}: {}", job.id, job.input_path);
+        let result = match tools {
+            (Some(ffmpeg_path), Some(ffprobe_path)) => run_job(&ffmpeg_path, &ffprobe_path, &job, cancel).await,
+            _ => Err("FFmpeg not found".to_string()),
+        };
+        match &result {
+            Ok(_) => log::info!("任务完成 {}", job.id),
+            Err(e) if e == "EXPORT_CANCELLED" => {}
+            Err(e) => log::warn!("任务失败 {}: {}", job.id, e),
+        }
+
+        let mut queue = QUEUE.lock().unwrap();
+        if let Some(entry) = queue.finish(&job.id, result) {
+            queue.save();
+            queue.notify(&entry);
+        }
+    }
+}
+
+async fn run_job(ffmpeg_path: &str, ffprobe_path: &str, job: &Job, cancel: Arc<AtomicBool>) -> Result<ProcessResult, String> {
+    let progress = JobProgressSink { id: job.id.clone() };
+    let result: Result<ProcessResult, Box<dyn std::error::Error>> = match &job.segments {
+        Some(segments) => video::remove_silence_from_video(
+            ffmpeg_path,
+            ffprobe_path,
+            &job.input_path,
+            &job.output_path,
+            segments,
+            &job.settings.export,
+            &progress,
+            cancel,
+        ).await,
+        None => video::process_file(
+            ffmpeg_path,
+            ffprobe_path,
+            &job.input_path,
+            &job.output_path,
+            &job.settings,
+            &progress,
+            cancel,
+        ).await,
+    };
+    result.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, status: JobStatus) -> Job {
+        Job {
+            id: id.to_string(),
+            input_path: format!("{}.mp4", id),
+            output_path: format!("{}_out.mp4", id),
+            settings: ProcessSettings::default(),
+            segments: None,
+            status,
+            created_at: String::new(),
+            started_at: Some("earlier".to_string()),
+            finished_at: None,
+            result: None,
+            error: None,
+            progress: None,
+        }
+    }
+
+    fn queue(jobs: Vec<Job>) -> JobQueue {
+        JobQueue { jobs, ..JobQueue::default() }
+    }
+
+    fn statuses(queue: &JobQueue) -> Vec<JobStatus> {
+        queue.jobs.iter().map(|j| j.status).collect()
+    }
+
+    #[test]
+    fn restore_requeues_interrupted_jobs() {
+        let mut queue = JobQueue::default();
+        queue.restore(vec![job("a", JobStatus::Running), job("b", JobStatus::Paused), job("c", JobStatus::Completed)]);
+        assert_eq!(statuses(&queue), vec![JobStatus::Queued, JobStatus::Paused, JobStatus::Completed]);
+        assert_eq!(queue.jobs[0].started_at, None);
+    }
+
+    #[test]
+    fn start_next_takes_first_queued_job() {
+        let mut queue = queue(vec![job("a", JobStatus::Paused), job("b", JobStatus::Queued), job("c", JobStatus::Queued)]);
+        let (started, cancel) = queue.start_next().unwrap();
+        assert_eq!(started.id, "b");
+        assert!(!cancel.load(Ordering::SeqCst));
+        assert_eq!(statuses(&queue), vec![JobStatus::Paused, JobStatus::Running, JobStatus::Queued]);
+        assert_eq!(queue.running.as_ref().map(|r| r.id.as_str()), Some("b"));
+    }
+
+    #[test]
+    fn pausing_running_job_ends_paused() {
+        let mut queue = queue(vec![job("a", JobStatus::Queued)]);
+        let (_, cancel) = queue.start_next().unwrap();
+
+        let (stopped, changed) = queue.stop("a", true).unwrap();
+        assert!(!changed);
+        assert_eq!(stopped.status, JobStatus::Running);
+        assert!(cancel.load(Ordering::SeqCst));
+
+        let finished = queue.finish("a", Err("EXPORT_CANCELLED".to_string())).unwrap();
+        assert_eq!(finished.status, JobStatus::Paused);
+        assert_eq!(finished.started_at, None);
+        assert_eq!(finished.finished_at, None);
+        assert!(queue.running.is_none());
+
+        assert_eq!(queue.resume("a").unwrap().status, JobStatus::Queued);
+    }
+
+    #[test]
+    fn cancelling_running_job_ends_cancelled() {
+        let mut queue = queue(vec![job("a", JobStatus::Queued)]);
+        queue.start_next().unwrap();
+        queue.stop("a", false).unwrap();
+        let finished = queue.finish("a", Err("EXPORT_CANCELLED".to_string())).unwrap();
+        assert_eq!(finished.status, JobStatus::Cancelled);
+        assert!(finished.finished_at.is_some());
+    }
+
+    #[test]
+    fn finish_records_failure() {
+        let mut queue = queue(vec![job("a", JobStatus::Queued)]);
+        queue.start_next().unwrap();
+        let finished = queue.finish("a", Err("编码失败".to_string())).unwrap();
+        assert_eq!(finished.status, JobStatus::Failed);
+        assert_eq!(finished.error.as_deref(), Some("编码失败"));
+    }
+
+    #[test]
+    fn stopping_idle_jobs_changes_state_directly() {
+        let mut queue = queue(vec![job("a", JobStatus::Queued), job("b", JobStatus::Paused), job("c", JobStatus::Completed)]);
+        let (paused, changed) = queue.stop("a", true).unwrap();
+        assert!(changed);
+        assert_eq!(paused.status, JobStatus::Paused);
+        let (cancelled, changed) = queue.stop("b", false).unwrap();
+        assert!(changed);
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert!(queue.stop("c", false).is_err());
+        assert!(queue.stop("missing", false).is_err());
+        assert!(queue.resume("c").is_err());
+    }
+
+    #[test]
+    fn remove_finished_keeps_unfinished_jobs() {
+        let mut queue = queue(vec![
+            job("a", JobStatus::Completed),
+            job("b", JobStatus::Failed),
+            job("c", JobStatus::Queued),
+            job("d", JobStatus::Cancelled),
+        ]);
+        assert_eq!(queue.remove_finished(Some("b")), 1);
+        assert_eq!(queue.remove_finished(Some("c")), 0);
+        assert_eq!(queue.remove_finished(None), 2);
+        assert_eq!(statuses(&queue), vec![JobStatus::Queued]);
+    }
+}
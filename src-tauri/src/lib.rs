@@ -2,18 +2,27 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 // 导出模块
+// 界面相关模块依赖 Tauri，只在 gui 特性下编译；audio/video/utils 为命令行版本共用的处理核心
+#[cfg(feature = "gui")]
 pub mod app;
+#[cfg(feature = "gui")]
 pub mod commands;
 pub mod audio;
 pub mod video;
+#[cfg(feature = "gui")]
+pub mod jobs;
+#[cfg(feature = "gui")]
+pub mod watch;
 pub mod utils;
 
 // 重新导出
+#[cfg(feature = "gui")]
 pub use app::run_app;
 // 不要使用通配符重导出，以避免名称冲突 (e.g. get_video_info)
 // 如果其他模块需要访问，可以通过 silence_cutter::commands::xxx 访问
 
 // 主运行函数
+#[cfg(feature = "gui")]
 pub fn run() -> tauri::Result<()> {
     app::run_app()
 }
\ No newline at end of file
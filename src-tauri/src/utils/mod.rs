@@ -1,7 +1,9 @@
 // src-tauri/src/utils/mod.rs
 // 工具函数模块
 
+#[cfg(feature = "gui")]
 pub mod sidecar;
+pub mod progress;
 use std::path::Path;
 use std::fs;
 use std::io;
@@ -0,0 +1,261 @@
+// src-tauri/src/utils/progress.rs
+// 进度上报
+// 处理流程不直接依赖 tauri::Window：界面把进度转发为窗口事件，命令行输出到 stderr，
+// 测试或其它调用方可以通过通道接收，不关心进度时使用 NoProgress
+
+use crate::audio::SilenceSegment;
+use serde::Serialize;
+#[cfg(feature = "gui")]
+use tauri::Emitter;
+
+// 分析阶段
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    Extracting,
+    Detecting,
+}
+
+// 某个阶段的进度 (analysis-progress / video-progress)
+#[derive(Debug, Serialize, Clone)]
+pub struct StageProgress {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<Stage>,
+    pub percent: f64,
+    pub message: String,
+    // 预计剩余时间 (秒)，未知时不发送
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta: Option<f64>,
+}
+
+impl StageProgress {
+    pub fn new(percent: f64, message: impl Into<String>) -> Self {
+        Self { stage: None, percent, message: message.into(), eta: None }
+    }
+
+    pub fn with_stage(mut self, stage: Stage) -> Self {
+        self.stage = Some(stage);
+        self
+    }
+
+    pub fn with_eta(mut self, eta: f64) -> Self {
+        self.eta = Some(eta);
+        self
+    }
+}
+
+// 流式分析过程中的波形与已确定的静音片段预览 (audio-waveform-step)
+#[derive(Debug, Serialize, Clone)]
+pub struct WaveformStep {
+    pub peaks: Vec<f32>,
+    // 已分析的比例 (0-1)
+    pub progress: f64,
+    pub silences: Vec<SilenceSegment>,
+}
+
+// 流式分析完成 (audio-waveform-done)
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveformDone {
+    pub duration: f64,
+    pub total_samples: u64,
+    #[serde(rename = "cache_id")]
+    pub cache_id: String,
+    // 峰值过多时为空，前端按缩放级别另行获取
+    pub peaks: Vec<f32>,
+    pub silences: Vec<SilenceSegment>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum BatchStage {
+    Processing,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+// 批量处理进度 (batch-progress)
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgress {
+    pub index: usize,
+    pub total: usize,
+    pub input_path: String,
+    pub stage: BatchStage,
+    pub message: String,
+    pub percent: f64,
+}
+
+// 进度事件，序列化后只包含事件内容，事件名由 name() 给出
+#[derive(Debug, Serialize, Clone)]
+#[serde(untagged)]
+pub enum ProgressEvent {
+    // 音频提取/分析
+    Analysis(StageProgress),
+    // 导出
+    Export(StageProgress),
+    WaveformStep(WaveformStep),
+    WaveformDone(WaveformDone),
+    Batch(BatchProgress),
+}
+
+impl ProgressEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProgressEvent::Analysis(_) => "analysis-progress",
+            ProgressEvent::Export(_) => "video-progress",
+            ProgressEvent::WaveformStep(_) => "audio-waveform-step",
+            ProgressEvent::WaveformDone(_) => "audio-waveform-done",
+            ProgressEvent::Batch(_) => "batch-progress",
+        }
+    }
+
+    pub fn export(percent: f64, message: impl Into<String>) -> Self {
+        ProgressEvent::Export(StageProgress::new(percent, message))
+    }
+
+    pub fn export_with_eta(percent: f64, message: impl Into<String>, eta: f64) -> Self {
+        ProgressEvent::Export(StageProgress::new(percent, message).with_eta(eta))
+    }
+}
+
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, event: ProgressEvent);
+}
+
+// 界面：转发为窗口事件
+#[cfg(feature = "gui")]
+impl ProgressSink for tauri::Window {
+    fn report(&self, event: ProgressEvent) {
+        let _ = self.emit(event.name(), &event);
+    }
+}
+
+// 通道：由调用方在另一端接收
+impl ProgressSink for tokio::sync::mpsc::UnboundedSender<ProgressEvent> {
+    fn report(&self, event: ProgressEvent) {
+        let _ = self.send(event);
+    }
+}
+
+// 不关心进度
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn report(&self, _event: ProgressEvent) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::video::{self, ProcessSettings};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    fn drain(rx: &mut mpsc::UnboundedReceiver<ProgressEvent>) -> Vec<ProgressEvent> {
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn batch_reports_each_file_through_channel() {
+        let output_dir = tempfile::tempdir().unwrap();
+        let inputs = vec!["/nonexistent/a.mp4".to_string(), "/nonexistent/b.mp4".to_string()];
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let results = video::batch_process_videos(
+            "/nonexistent/ffmpeg",
+            "/nonexistent/ffprobe",
+            &inputs,
+            output_dir.path().to_str().unwrap(),
+            &ProcessSettings::default(),
+            &tx,
+            Arc::new(AtomicBool::new(false)),
+        ).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| !r.success));
+
+        let batch: Vec<(usize, BatchStage, String, f64)> = drain(&mut rx).into_iter()
+            .filter_map(|event| match event {
+                ProgressEvent::Batch(p) => Some((p.index, p.stage, p.input_path, p.percent)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(batch, vec![
+            (0, BatchStage::Processing, inputs[0].clone(), 0.0),
+            (1, BatchStage::Failed, inputs[0].clone(), 50.0),
+            (1, BatchStage::Processing, inputs[1].clone(), 50.0),
+            (2, BatchStage::Failed, inputs[1].clone(), 100.0),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn cancelled_batch_reports_nothing() {
+        let output_dir = tempfile::tempdir().unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let results = video::batch_process_videos(
+            "/nonexistent/ffmpeg",
+            "/nonexistent/ffprobe",
+            &["/nonexistent/a.mp4".to_string()],
+            output_dir.path().to_str().unwrap(),
+            &ProcessSettings::default(),
+            &tx,
+            Arc::new(AtomicBool::new(true)),
+        ).await.unwrap();
+        assert_eq!(results[0].error_message.as_deref(), Some("EXPORT_CANCELLED"));
+        assert!(drain(&mut rx).is_empty());
+    }
+
+    #[test]
+    fn batch_event_uses_frontend_field_names() {
+        let event = ProgressEvent::Batch(BatchProgress {
+            index: 0,
+            total: 2,
+            input_path: "a.mp4".to_string(),
+            stage: BatchStage::Processing,
+            message: String::new(),
+            percent: 0.0,
+        });
+        assert_eq!(event.name(), "batch-progress");
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["inputPath"], "a.mp4");
+        assert_eq!(value["stage"], "processing");
+        assert_eq!(value["total"], 2);
+    }
+
+    #[test]
+    fn waveform_done_keeps_snake_case_cache_id() {
+        let event = ProgressEvent::WaveformDone(WaveformDone {
+            duration: 1.0,
+            total_samples: 16000,
+            cache_id: "a.mp4".to_string(),
+            peaks: Vec::new(),
+            silences: Vec::new(),
+        });
+        assert_eq!(event.name(), "audio-waveform-done");
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["totalSamples"], 16000);
+        assert_eq!(value["cache_id"], "a.mp4");
+        assert!(value.get("cacheId").is_none());
+    }
+
+    #[test]
+    fn stage_progress_omits_unknown_stage_and_eta() {
+        let plain = serde_json::to_value(ProgressEvent::export(10.0, "导出中")).unwrap();
+        assert!(plain.get("eta").is_none());
+        assert!(plain.get("stage").is_none());
+        assert_eq!(plain["percent"], 10.0);
+
+        let event = ProgressEvent::Analysis(StageProgress::new(50.0, "分析中").with_stage(Stage::Extracting).with_eta(3.0));
+        assert_eq!(event.name(), "analysis-progress");
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["stage"], "extracting");
+        assert_eq!(value["eta"], 3.0);
+    }
+}
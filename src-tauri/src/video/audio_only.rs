@@ -0,0 +1,137 @@
+// 纯音频导出 (播客模式)
+// 输入没有视频或只需要音频时，直接从原文件解码音频，按保留片段以采样精度裁剪拼接，
+// 一次编码写出目标格式，不经过 MPEG-TS 分批与视频封装
+
+use super::transitions::{self, AudioTransition};
+use super::{output_duration, RenderContext};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::Ordering;
+use crate::utils::progress::ProgressEvent;
+use tempfile::NamedTempFile;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command as TokioCommand;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AudioFormat {
+    Wav,
+    Flac,
+    Mp3,
+    Opus,
+    #[default]
+    M4a,
+}
+
+impl AudioFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Opus => "opus",
+            AudioFormat::M4a => "m4a",
+        }
+    }
+
+    // 按输出文件扩展名推断格式 (纯音频输入未指定格式时使用)
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "wav" => Some(AudioFormat::Wav),
+            "flac" => Some(AudioFormat::Flac),
+            "mp3" => Some(AudioFormat::Mp3),
+            "opus" | "ogg" => Some(AudioFormat::Opus),
+            "m4a" | "aac" => Some(AudioFormat::M4a),
+            _ => None,
+        }
+    }
+
+    fn codec_args(&self) -> &'static [&'static str] {
+        match self {
+            AudioFormat::Wav => &["-c:a", "pcm_s16le", "-f", "wav"],
+            AudioFormat::Flac => &["-c:a", "flac", "-f", "flac"],
+            AudioFormat::Mp3 => &["-c:a", "libmp3lame", "-q:a", "2", "-f", "mp3"],
+            AudioFormat::Opus => &["-c:a", "libopus", "-b:a", "96k", "-f", "ogg"],
+            AudioFormat::M4a => &["-c:a", "aac", "-b:a", "192k", "-movflags", "+faststart", "-f", "ipod"],
+        }
+    }
+}
+
+// 裁剪拼接所有保留片段并编码为目标格式
+pub async fn export_audio(
+    ctx: &RenderContext<'_>,
+    format: AudioFormat,
+    transition: AudioTransition,
+    duration: f64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let RenderContext { ffmpeg_path, input, output, segments, progress, cancel_signal } = *ctx;
+    // atrim 按时间戳换算到采样点裁剪，不做输入端 seek，保证切点精确到采样
+    let filter = transitions::audio_filter(segments, transition, duration, 0.0, "0:a:0");
+
+    // 片段很多时命令行会超长，过滤器写入脚本文件
+    let mut script = NamedTempFile::new()?;
+    script.write_all(filter.as_bytes())?;
+    script.flush()?;
+
+    let total_duration = output_duration(segments);
+    log::info!("纯音频导出 {} 个片段 ({:?}), 输出时长约 {:.2}s", segments.len(), format, total_duration);
+
+    let start_time = std::time::Instant::now();
+    let mut child = TokioCommand::new(ffmpeg_path)
+        .args(["-nostdin", "-i", input])
+        .args(super::filter_script_args(ffmpeg_path, script.path()).await)
+        .args(["-map", "[fa]", "-vn"])
+        .args(format.codec_args())
+        .args(["-progress", "pipe:1", "-nostats", "-y", output])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let stdout = child.stdout.take().ok_or("无法打开 ffmpeg stdout")?;
+    let mut lines = BufReader::new(stdout).lines();
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let line = match line? {
+                    Some(line) => line,
+                    None => break,
+                };
+                if let Some(value) = line.strip_prefix("out_time_us=") {
+                    if let Ok(us) = value.trim().parse::<f64>() {
+                        let done = us / 1_000_000.0;
+                        let percent = 5.0 + (done / total_duration.max(0.001)).min(1.0) * 90.0;
+                        // 按已用时间与已输出时长的比例估算剩余时间
+                        let eta = if done > 0.0 {
+                            start_time.elapsed().as_secs_f64() / done * (total_duration - done).max(0.0)
+                        } else {
+                            0.0
+                        };
+                        progress.report(ProgressEvent::export_with_eta(
+                            percent,
+                            format!("正在导出音频: {:.0}s / {:.0}s", done, total_duration),
+                            eta,
+                        ));
+                    }
+                }
+            }
+            _ = tokio::time::sleep(tokio::time::Duration::from_millis(100)) => {
+                if cancel_signal.load(Ordering::SeqCst) {
+                    let _ = child.kill().await;
+                    let _ = std::fs::remove_file(output);
+                    log::warn!("任务被用户取消，已停止音频导出");
+                    return Err("EXPORT_CANCELLED".into());
+                }
+            }
+        }
+    }
+
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err("音频导出失败".into());
+    }
+    Ok(())
+}
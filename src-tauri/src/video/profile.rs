@@ -0,0 +1,367 @@
+// 导出编码配置
+// 视频编码、码率控制、预设、音频编码、像素格式与封装格式，开始导出前先校验组合是否可用
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command as TokioCommand;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum VideoCodec {
+    H264,
+    H265,
+    Vp9,
+    Av1,
+    ProRes,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AudioCodec {
+    Aac,
+    Opus,
+    Mp3,
+    Flac,
+    Pcm,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Container {
+    Mp4,
+    Mov,
+    Mkv,
+    Webm,
+}
+
+// 码率控制：恒定质量或目标码率
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RateControl {
+    Crf { value: u32 },
+    Bitrate { kbps: u32 },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProfile {
+    pub video_codec: VideoCodec,
+    // 为空时使用各编码器的默认质量
+    #[serde(default)]
+    pub rate_control: Option<RateControl>,
+    // x264/x265 为 ultrafast~veryslow，VP9 为 good/best/realtime，AV1 为 0~13，ProRes 为 proxy/lt/standard/hq/4444
+    #[serde(default)]
+    pub preset: Option<String>,
+    pub audio_codec: AudioCodec,
+    #[serde(default)]
+    pub audio_bitrate_kbps: Option<u32>,
+    #[serde(default)]
+    pub pix_fmt: Option<String>,
+    pub container: Container,
+}
+
+const X26X_PRESETS: &[&str] = &["ultrafast", "superfast", "veryfast", "faster", "fast", "medium", "slow", "slower", "veryslow"];
+const VP9_DEADLINES: &[&str] = &["good", "best", "realtime"];
+const PRORES_PROFILES: &[&str] = &["proxy", "lt", "standard", "hq", "4444"];
+
+impl VideoCodec {
+    fn encoder(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "libx264",
+            VideoCodec::H265 => "libx265",
+            VideoCodec::Vp9 => "libvpx-vp9",
+            VideoCodec::Av1 => "libsvtav1",
+            VideoCodec::ProRes => "prores_ks",
+        }
+    }
+
+    fn pix_fmts(&self) -> &'static [&'static str] {
+        match self {
+            VideoCodec::H264 => &["yuv420p", "yuv422p", "yuv444p", "yuv420p10le"],
+            VideoCodec::H265 => &["yuv420p", "yuv422p", "yuv444p", "yuv420p10le", "yuv422p10le"],
+            VideoCodec::Vp9 => &["yuv420p", "yuv444p", "yuv420p10le"],
+            VideoCodec::Av1 => &["yuv420p", "yuv420p10le"],
+            VideoCodec::ProRes => &["yuv422p10le", "yuv444p10le"],
+        }
+    }
+
+    fn crf_range(&self) -> Option<(u32, u32)> {
+        match self {
+            VideoCodec::H264 | VideoCodec::H265 => Some((0, 51)),
+            VideoCodec::Vp9 | VideoCodec::Av1 => Some((0, 63)),
+            VideoCodec::ProRes => None,
+        }
+    }
+}
+
+impl AudioCodec {
+    fn encoder(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "aac",
+            AudioCodec::Opus => "libopus",
+            AudioCodec::Mp3 => "libmp3lame",
+            AudioCodec::Flac => "flac",
+            AudioCodec::Pcm => "pcm_s16le",
+        }
+    }
+
+    fn is_lossless(&self) -> bool {
+        matches!(self, AudioCodec::Flac | AudioCodec::Pcm)
+    }
+
+    fn default_bitrate_kbps(&self) -> u32 {
+        match self {
+            AudioCodec::Opus => 96,
+            _ => 128,
+        }
+    }
+}
+
+impl Container {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mov => "mov",
+            Container::Mkv => "mkv",
+            Container::Webm => "webm",
+        }
+    }
+
+    // 按输出文件扩展名推断封装格式 (未指定编码配置时使用)
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "mp4" | "m4v" => Some(Container::Mp4),
+            "mov" => Some(Container::Mov),
+            "mkv" => Some(Container::Mkv),
+            "webm" => Some(Container::Webm),
+            _ => None,
+        }
+    }
+
+    // ffmpeg 的封装器名称，输出时显式指定，不依赖输出文件扩展名
+    pub fn muxer(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mov => "mov",
+            Container::Mkv => "matroska",
+            Container::Webm => "webm",
+        }
+    }
+
+    // -movflags +faststart 只对 MP4/MOV 有意义
+    pub fn supports_faststart(&self) -> bool {
+        matches!(self, Container::Mp4 | Container::Mov)
+    }
+
+    fn supports_video(&self, codec: VideoCodec) -> bool {
+        match self {
+            Container::Mp4 => matches!(codec, VideoCodec::H264 | VideoCodec::H265 | VideoCodec::Av1 | VideoCodec::Vp9),
+            Container::Mov => matches!(codec, VideoCodec::H264 | VideoCodec::H265 | VideoCodec::ProRes),
+            Container::Mkv => true,
+            Container::Webm => matches!(codec, VideoCodec::Vp9 | VideoCodec::Av1),
+        }
+    }
+
+    fn supports_audio(&self, codec: AudioCodec) -> bool {
+        match self {
+            // MP4 中的 Opus 在 Apple 播放器上无法播放，需要 Opus 时使用 MKV/WebM
+            Container::Mp4 => matches!(codec, AudioCodec::Aac | AudioCodec::Mp3),
+            Container::Mov => matches!(codec, AudioCodec::Aac | AudioCodec::Mp3 | AudioCodec::Pcm),
+            Container::Mkv => true,
+            Container::Webm => matches!(codec, AudioCodec::Opus),
+        }
+    }
+}
+
+impl ExportProfile {
+    // 校验参数组合 (不依赖 ffmpeg)
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.container.supports_video(self.video_codec) {
+            return Err(format!("{:?} 封装不支持 {:?} 视频编码", self.container, self.video_codec).into());
+        }
+        if !self.container.supports_audio(self.audio_codec) {
+            return Err(format!("{:?} 封装不支持 {:?} 音频编码", self.container, self.audio_codec).into());
+        }
+
+        match (self.rate_control, self.video_codec.crf_range()) {
+            (Some(_), None) => {
+                return Err("ProRes 的质量由 preset (proxy/lt/standard/hq/4444) 决定，不能指定 CRF 或码率".into());
+            }
+            (Some(RateControl::Crf { value }), Some((min, max))) if value < min || value > max => {
+                return Err(format!("{:?} 的 CRF 必须在 {} ~ {} 之间", self.video_codec, min, max).into());
+            }
+            (Some(RateControl::Bitrate { kbps }), _) if !(100..=200_000).contains(&kbps) => {
+                return Err("视频码率必须在 100 ~ 200000 kbps 之间".into());
+            }
+            _ => {}
+        }
+
+        if let Some(preset) = &self.preset {
+            let valid = match self.video_codec {
+                VideoCodec::H264 | VideoCodec::H265 => X26X_PRESETS.contains(&preset.as_str()),
+                VideoCodec::Vp9 => VP9_DEADLINES.contains(&preset.as_str()),
+                VideoCodec::Av1 => preset.parse::<u32>().is_ok_and(|p| p <= 13),
+                VideoCodec::ProRes => PRORES_PROFILES.contains(&preset.as_str()),
+            };
+            if !valid {
+                return Err(format!("{:?} 不支持预设 \"{}\"", self.video_codec, preset).into());
+            }
+        }
+
+        if let Some(pix_fmt) = &self.pix_fmt {
+            if !self.video_codec.pix_fmts().contains(&pix_fmt.as_str()) {
+                return Err(format!("{:?} 不支持像素格式 {}，可选: {}", self.video_codec, pix_fmt, self.video_codec.pix_fmts().join(", ")).into());
+            }
+        }
+
+        if let Some(kbps) = self.audio_bitrate_kbps {
+            if self.audio_codec.is_lossless() {
+                return Err(format!("{:?} 为无损音频，不能指定码率", self.audio_codec).into());
+            }
+            if !(32..=512).contains(&kbps) {
+                return Err("音频码率必须在 32 ~ 512 kbps 之间".into());
+            }
+        }
+
+        Ok(())
+    }
+
+    // 检查当前 ffmpeg 是否编译了所需编码器
+    pub async fn check_encoders(&self, ffmpeg_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let output = TokioCommand::new(ffmpeg_path)
+            .args(["-hide_banner", "-encoders"])
+            .output()
+            .await?;
+        let listing = String::from_utf8_lossy(&output.stdout);
+        let available = |name: &str| listing.lines().any(|line| line.split_whitespace().nth(1) == Some(name));
+
+        for encoder in [self.video_codec.encoder(), self.audio_codec.encoder()] {
+            if !available(encoder) {
+                return Err(format!("当前 FFmpeg 不支持编码器 {}", encoder).into());
+            }
+        }
+        Ok(())
+    }
+
+    // 视频编码参数
+    pub fn video_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = vec!["-c:v".into(), self.video_codec.encoder().into()];
+
+        match self.video_codec {
+            VideoCodec::ProRes => {
+                let profile = self.preset.as_deref().unwrap_or("standard");
+                let index = PRORES_PROFILES.iter().position(|p| *p == profile).unwrap_or(2);
+                args.extend(["-profile:v".into(), index.to_string()]);
+            }
+            VideoCodec::Vp9 => {
+                match self.rate_control {
+                    Some(RateControl::Bitrate { kbps }) => args.extend(["-b:v".into(), format!("{}k", kbps)]),
+                    Some(RateControl::Crf { value }) => args.extend(["-crf".into(), value.to_string(), "-b:v".into(), "0".into()]),
+                    None => args.extend(["-crf".into(), "31".into(), "-b:v".into(), "0".into()]),
+                }
+                args.extend(["-deadline".into(), self.preset.clone().unwrap_or_else(|| "good".into()), "-row-mt".into(), "1".into()]);
+            }
+            VideoCodec::H264 | VideoCodec::H265 | VideoCodec::Av1 => {
+                let default_crf = match self.video_codec {
+                    VideoCodec::H264 => 23,
+                    VideoCodec::H265 => 28,
+                    _ => 35,
+                };
+                match self.rate_control {
+                    Some(RateControl::Bitrate { kbps }) => args.extend(["-b:v".into(), format!("{}k", kbps)]),
+                    Some(RateControl::Crf { value }) => args.extend(["-crf".into(), value.to_string()]),
+                    None => args.extend(["-crf".into(), default_crf.to_string()]),
+                }
+                let default_preset = if self.video_codec == VideoCodec::Av1 { "8" } else { "medium" };
+                args.extend(["-preset".into(), self.preset.clone().unwrap_or_else(|| default_preset.into())]);
+            }
+        }
+
+        // Apple 设备只识别 hvc1 标签的 HEVC
+        if self.video_codec == VideoCodec::H265 && matches!(self.container, Container::Mp4 | Container::Mov) {
+            args.extend(["-tag:v".into(), "hvc1".into()]);
+        }
+        if let Some(pix_fmt) = &self.pix_fmt {
+            args.extend(["-pix_fmt".into(), pix_fmt.clone()]);
+        }
+        args
+    }
+
+    // 音频编码参数
+    pub fn audio_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = vec!["-c:a".into(), self.audio_codec.encoder().into()];
+        if !self.audio_codec.is_lossless() {
+            let kbps = self.audio_bitrate_kbps.unwrap_or_else(|| self.audio_codec.default_bitrate_kbps());
+            args.extend(["-b:a".into(), format!("{}k", kbps)]);
+        }
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(video_codec: VideoCodec, audio_codec: AudioCodec, container: Container) -> ExportProfile {
+        ExportProfile {
+            video_codec,
+            rate_control: None,
+            preset: None,
+            audio_codec,
+            audio_bitrate_kbps: None,
+            pix_fmt: None,
+            container,
+        }
+    }
+
+    fn h264() -> ExportProfile {
+        profile(VideoCodec::H264, AudioCodec::Aac, Container::Mp4)
+    }
+
+    #[test]
+    fn valid_profiles_pass() {
+        let cases = [
+            h264(),
+            profile(VideoCodec::ProRes, AudioCodec::Pcm, Container::Mov),
+            profile(VideoCodec::Vp9, AudioCodec::Opus, Container::Webm),
+            profile(VideoCodec::Av1, AudioCodec::Flac, Container::Mkv),
+            ExportProfile { rate_control: Some(RateControl::Crf { value: 51 }), preset: Some("slow".into()), ..h264() },
+            ExportProfile { rate_control: Some(RateControl::Crf { value: 63 }), preset: Some("13".into()), ..profile(VideoCodec::Av1, AudioCodec::Opus, Container::Mkv) },
+            ExportProfile { preset: Some("hq".into()), pix_fmt: Some("yuv444p10le".into()), ..profile(VideoCodec::ProRes, AudioCodec::Aac, Container::Mov) },
+            ExportProfile { audio_bitrate_kbps: Some(320), ..h264() },
+        ];
+        for case in cases {
+            assert!(case.validate().is_ok(), "{:?}", case);
+        }
+    }
+
+    #[test]
+    fn invalid_profiles_report_reason() {
+        let cases = [
+            (profile(VideoCodec::ProRes, AudioCodec::Aac, Container::Mp4), "视频编码"),
+            (profile(VideoCodec::H265, AudioCodec::Aac, Container::Webm), "视频编码"),
+            (profile(VideoCodec::H264, AudioCodec::Opus, Container::Mp4), "音频编码"),
+            (profile(VideoCodec::Vp9, AudioCodec::Aac, Container::Webm), "音频编码"),
+            (ExportProfile { rate_control: Some(RateControl::Crf { value: 52 }), ..h264() }, "0 ~ 51"),
+            (ExportProfile { rate_control: Some(RateControl::Crf { value: 52 }), ..profile(VideoCodec::H265, AudioCodec::Aac, Container::Mp4) }, "0 ~ 51"),
+            (ExportProfile { rate_control: Some(RateControl::Crf { value: 64 }), ..profile(VideoCodec::Vp9, AudioCodec::Opus, Container::Webm) }, "0 ~ 63"),
+            (ExportProfile { rate_control: Some(RateControl::Crf { value: 64 }), ..profile(VideoCodec::Av1, AudioCodec::Opus, Container::Mkv) }, "0 ~ 63"),
+            (ExportProfile { rate_control: Some(RateControl::Crf { value: 10 }), ..profile(VideoCodec::ProRes, AudioCodec::Pcm, Container::Mov) }, "ProRes"),
+            (ExportProfile { rate_control: Some(RateControl::Bitrate { kbps: 8000 }), ..profile(VideoCodec::ProRes, AudioCodec::Pcm, Container::Mov) }, "ProRes"),
+            (ExportProfile { rate_control: Some(RateControl::Bitrate { kbps: 50 }), ..h264() }, "视频码率"),
+            (ExportProfile { audio_bitrate_kbps: Some(192), ..profile(VideoCodec::H264, AudioCodec::Flac, Container::Mkv) }, "无损音频"),
+            (ExportProfile { audio_bitrate_kbps: Some(1536), ..profile(VideoCodec::ProRes, AudioCodec::Pcm, Container::Mov) }, "无损音频"),
+            (ExportProfile { audio_bitrate_kbps: Some(16), ..h264() }, "音频码率"),
+            (ExportProfile { preset: Some("placebo".into()), ..h264() }, "预设"),
+            (ExportProfile { preset: Some("medium".into()), ..profile(VideoCodec::Vp9, AudioCodec::Opus, Container::Webm) }, "预设"),
+            (ExportProfile { preset: Some("14".into()), ..profile(VideoCodec::Av1, AudioCodec::Opus, Container::Mkv) }, "预设"),
+            (ExportProfile { preset: Some("ultra".into()), ..profile(VideoCodec::ProRes, AudioCodec::Pcm, Container::Mov) }, "预设"),
+            (ExportProfile { pix_fmt: Some("yuv420p".into()), ..profile(VideoCodec::ProRes, AudioCodec::Pcm, Container::Mov) }, "像素格式"),
+            (ExportProfile { pix_fmt: Some("rgb24".into()), ..h264() }, "像素格式"),
+        ];
+        for (case, reason) in cases {
+            let error = case.validate().expect_err(&format!("{:?}", case)).to_string();
+            assert!(error.contains(reason), "{:?}: {}", case, error);
+        }
+    }
+}
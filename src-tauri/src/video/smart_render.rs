@@ -0,0 +1,486 @@
+// 智能渲染导出
+// 每个保留片段内部完整的 GOP 直接拷贝，只重新编码片段开头和结尾不完整的 GOP，
+// 编码参数 (编码器、profile、level、像素格式、分辨率、SAR、帧率) 取自源视频，保证拼接后码流兼容；
+// 当前 ffmpeg 无法输出相同参数时由调用方回退到完整重新编码，拼接完成后再核对一次输出流参数。
+// 视频按片段拼接，音频单独按采样精确裁剪后编码，最后一起封装
+
+use super::transitions::{self, AudioTransition};
+use super::{RenderContext, SpeechSegment};
+use serde_json;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use crate::utils::progress::ProgressEvent;
+use tempfile::NamedTempFile;
+use tokio::process::Command as TokioCommand;
+
+// 剪辑点与关键帧距离小于此值时视为对齐
+const ALIGN_EPSILON: f64 = 0.001;
+// 可拷贝的中间部分短于此值时整段重新编码，不值得拆成三段
+const MIN_COPY_SPAN: f64 = 1.0;
+// 并发的 ffmpeg 进程数
+const MAX_CONCURRENT_PIECES: usize = 4;
+
+// 源视频流参数
+pub struct SourceVideo {
+    encoder: &'static str,
+    profile: Option<&'static str>,
+    // 编码器参数形式的 level (如 "4.1")
+    level: Option<String>,
+    pix_fmt: String,
+    frame_rate: Option<String>,
+    width: u64,
+    height: u64,
+    sample_aspect_ratio: Option<String>,
+    // 源视频流的时间基分母，MP4/MOV 输出沿用，避免拷贝部分的时间戳被重新量化
+    timescale: Option<u64>,
+    // ffprobe 原始的编码名与 profile，用于核对输出
+    codec_name: String,
+    profile_name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PieceKind {
+    Encode,
+    Copy,
+}
+
+#[derive(Debug, Clone)]
+struct Piece {
+    kind: PieceKind,
+    start: f64,
+    end: f64,
+}
+
+// 读取第一条视频流的参数
+async fn probe_video_stream(ffprobe_path: &str, input: &str) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let output = TokioCommand::new(ffprobe_path)
+        .args([
+            "-v", "error",
+            "-select_streams", "v:0",
+            "-show_entries", "stream=codec_name,profile,level,pix_fmt,width,height,sample_aspect_ratio,r_frame_rate,time_base",
+            "-of", "json",
+            input,
+        ])
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err("读取视频编码参数失败".into());
+    }
+
+    let mut json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    Ok(json["streams"][0].take())
+}
+
+// 读取源视频编码参数，不支持智能渲染或当前 ffmpeg 无法输出相同参数时返回 None
+pub async fn probe_source_video(ffmpeg_path: &str, ffprobe_path: &str, input: &str) -> Result<Option<SourceVideo>, Box<dyn std::error::Error>> {
+    let stream = probe_video_stream(ffprobe_path, input).await?;
+    let codec = stream["codec_name"].as_str().unwrap_or("");
+    let profile = stream["profile"].as_str().unwrap_or("");
+
+    // 拼接片段为 MPEG-TS，只支持能封装进 TS 且有对应编码器的格式
+    let (encoder, profile) = match codec {
+        "h264" => ("libx264", match profile {
+            "Baseline" | "Constrained Baseline" => Some("baseline"),
+            "Main" => Some("main"),
+            "High" => Some("high"),
+            "High 10" => Some("high10"),
+            "High 4:2:2" => Some("high422"),
+            "High 4:4:4 Predictive" => Some("high444"),
+            _ => None,
+        }),
+        "hevc" => ("libx265", match profile {
+            "Main" => Some("main"),
+            "Main 10" => Some("main10"),
+            _ => None,
+        }),
+        "mpeg2video" => ("mpeg2video", None),
+        _ => {
+            log::info!("智能渲染不支持的视频编码: {}", codec);
+            return Ok(None);
+        }
+    };
+    // profile 无法对应时编码出的片段与拷贝部分不兼容
+    if profile.is_none() && encoder != "mpeg2video" {
+        log::info!("智能渲染不支持的 {} profile: {}", codec, stream["profile"]);
+        return Ok(None);
+    }
+
+    let (pix_fmt, width, height) = match (stream["pix_fmt"].as_str(), stream["width"].as_u64(), stream["height"].as_u64()) {
+        (Some(pix_fmt), Some(width), Some(height)) => (pix_fmt.to_string(), width, height),
+        _ => {
+            log::warn!("无法读取源视频的像素格式与分辨率");
+            return Ok(None);
+        }
+    };
+    // 例如 10-bit 源而 ffmpeg 中的 libx264 只支持 8-bit
+    if !encoder_supports_pix_fmt(ffmpeg_path, encoder, &pix_fmt).await {
+        log::info!("编码器 {} 不支持像素格式 {}", encoder, pix_fmt);
+        return Ok(None);
+    }
+
+    let level = stream["level"].as_i64().filter(|&l| l > 0).and_then(|l| match codec {
+        // H.264 的 level 为 10 倍值，如 41 表示 4.1 (9 表示 1b，不单独设置)
+        "h264" if l >= 10 => Some(format!("{}.{}", l / 10, l % 10)),
+        // HEVC 的 level 为 30 倍值，如 123 表示 4.1
+        "hevc" => Some(format!("{}.{}", l / 30, l % 30 / 3)),
+        _ => None,
+    });
+
+    Ok(Some(SourceVideo {
+        encoder,
+        profile,
+        level,
+        pix_fmt,
+        frame_rate: stream["r_frame_rate"].as_str()
+            .filter(|r| r.split_once('/').is_some_and(|(n, d)| n != "0" && d != "0"))
+            .map(|s| s.to_string()),
+        width,
+        height,
+        sample_aspect_ratio: stream["sample_aspect_ratio"].as_str()
+            .filter(|r| r.split_once(':').is_some_and(|(n, d)| n != "0" && d != "0"))
+            .map(|s| s.to_string()),
+        timescale: stream["time_base"].as_str()
+            .and_then(|t| t.split_once('/'))
+            .and_then(|(_, d)| d.parse().ok()),
+        codec_name: codec.to_string(),
+        profile_name: stream["profile"].as_str().unwrap_or("").to_string(),
+    }))
+}
+
+// 编码器是否支持指定像素格式 (读取 ffmpeg -h encoder=... 的 Supported pixel formats)
+async fn encoder_supports_pix_fmt(ffmpeg_path: &str, encoder: &str, pix_fmt: &str) -> bool {
+    let output = match TokioCommand::new(ffmpeg_path)
+        .args(["-hide_banner", "-h", &format!("encoder={}", encoder)])
+        .output()
+        .await
+    {
+        Ok(output) => output,
+        Err(_) => return false,
+    };
+    let help = String::from_utf8_lossy(&output.stdout);
+    help.lines()
+        .filter_map(|line| line.trim().strip_prefix("Supported pixel formats:"))
+        .any(|formats| formats.split_whitespace().any(|f| f == pix_fmt))
+}
+
+// 核对拼接结果的视频流参数与源视频一致，不一致时由调用方改为完整重新编码
+pub async fn verify_output(ffprobe_path: &str, output: &str, source: &SourceVideo) -> bool {
+    let stream = match probe_video_stream(ffprobe_path, output).await {
+        Ok(stream) => stream,
+        Err(_) => return false,
+    };
+    let matches = stream["codec_name"].as_str() == Some(source.codec_name.as_str())
+        && stream["profile"].as_str().unwrap_or("") == source.profile_name
+        && stream["pix_fmt"].as_str() == Some(source.pix_fmt.as_str())
+        && stream["width"].as_u64() == Some(source.width)
+        && stream["height"].as_u64() == Some(source.height);
+    if !matches {
+        log::warn!("智能渲染输出与源视频参数不一致: {}", stream);
+    }
+    matches
+}
+
+// 把片段拆成 [编码头部][拷贝中间][编码尾部]
+fn plan_pieces(segments: &[SpeechSegment], keyframes: &[f64]) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    for segment in segments {
+        // 片段内第一个与最后一个关键帧
+        let first_key = keyframes.iter().find(|&&k| k >= segment.start - ALIGN_EPSILON).copied();
+        let last_key = keyframes.iter().rev().find(|&&k| k <= segment.end + ALIGN_EPSILON).copied();
+
+        match (first_key, last_key) {
+            (Some(copy_start), Some(copy_end)) if copy_end - copy_start >= MIN_COPY_SPAN => {
+                if copy_start - segment.start > ALIGN_EPSILON {
+                    pieces.push(Piece { kind: PieceKind::Encode, start: segment.start, end: copy_start });
+                }
+                pieces.push(Piece { kind: PieceKind::Copy, start: copy_start, end: copy_end });
+                if segment.end - copy_end > ALIGN_EPSILON {
+                    pieces.push(Piece { kind: PieceKind::Encode, start: copy_end, end: segment.end });
+                }
+            }
+            _ => pieces.push(Piece { kind: PieceKind::Encode, start: segment.start, end: segment.end }),
+        }
+    }
+    pieces
+}
+
+// 智能渲染主流程
+pub async fn render(
+    ctx: &RenderContext<'_>,
+    keyframes: &[f64],
+    source: &SourceVideo,
+    has_audio: bool,
+    transition: AudioTransition,
+    duration: f64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let segments = ctx.segments;
+    let pieces = plan_pieces(segments, keyframes);
+    let copied: f64 = pieces.iter().filter(|p| p.kind == PieceKind::Copy).map(|p| p.end - p.start).sum();
+    let total: f64 = pieces.iter().map(|p| p.end - p.start).sum();
+    log::info!("智能渲染: {} 个片段 -> {} 段, 直接拷贝 {:.1}s / {:.1}s",
+        segments.len(), pieces.len(), copied, total);
+
+    let mut temp_dir = PathBuf::from(ctx.output);
+    temp_dir.set_extension("smart_parts");
+    if temp_dir.exists() { let _ = fs::remove_dir_all(&temp_dir); }
+    fs::create_dir_all(&temp_dir)?;
+
+    let result = render_in(ctx, &pieces, source, has_audio, transition, duration, &temp_dir).await;
+    let _ = fs::remove_dir_all(&temp_dir);
+    result
+}
+
+async fn render_in(
+    ctx: &RenderContext<'_>,
+    pieces: &[Piece],
+    source: &SourceVideo,
+    has_audio: bool,
+    transition: AudioTransition,
+    duration: f64,
+    temp_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let RenderContext { ffmpeg_path, input, output, segments, progress, cancel_signal } = *ctx;
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_PIECES));
+    let mut tasks = tokio::task::JoinSet::new();
+    let start_time = std::time::Instant::now();
+
+    // 1. 并行生成视频片段
+    for (index, piece) in pieces.iter().enumerate() {
+        let args = piece_args(input, piece, source, &temp_dir.join(format!("piece_{}.ts", index)));
+        let ffmpeg_cmd = ffmpeg_path.to_string();
+        let sem = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = sem.acquire().await.map_err(|e| format!("Semaphore error: {}", e))?;
+            run_ffmpeg(&ffmpeg_cmd, &args).await
+        });
+    }
+
+    // 2. 音频按采样精确裁剪，和视频并行
+    let audio_path = temp_dir.join("audio.m4a");
+    if has_audio {
+        // 片段很多时命令行会超长，过滤器写入脚本文件，ffmpeg 结束后才删除
+        let filter = transitions::audio_filter(segments, transition, duration, 0.0, "0:a:0");
+        let mut script = NamedTempFile::new()?;
+        script.write_all(filter.as_bytes())?;
+        script.flush()?;
+        let args = audio_args(input, super::filter_script_args(ffmpeg_path, script.path()).await, &audio_path);
+        let ffmpeg_cmd = ffmpeg_path.to_string();
+        tasks.spawn(async move {
+            let result = run_ffmpeg(&ffmpeg_cmd, &args).await;
+            drop(script);
+            result
+        });
+    }
+
+    let total_tasks = tasks.len();
+    let mut completed = 0;
+    while completed < total_tasks {
+        tokio::select! {
+            res = tasks.join_next() => {
+                match res {
+                    Some(join_res) => {
+                        let piece_result = join_res.map_err(|e| format!("Parallel task panicked: {}", e))?;
+                        piece_result.map_err(|e| format!("Piece processing error: {}", e))?;
+                        completed += 1;
+                        let elapsed = start_time.elapsed().as_secs_f64();
+                        let eta = elapsed / completed as f64 * (total_tasks - completed) as f64;
+                        progress.report(ProgressEvent::export_with_eta(
+                            2.0 + completed as f64 / total_tasks as f64 * 90.0,
+                            format!("正在智能渲染: {}/{}", completed, total_tasks),
+                            eta,
+                        ));
+                    }
+                    None => break,
+                }
+            }
+            _ = tokio::time::sleep(tokio::time::Duration::from_millis(100)) => {
+                if cancel_signal.load(Ordering::SeqCst) {
+                    tasks.abort_all();
+                    log::warn!("任务被用户取消，正在清理临时文件...");
+                    return Err("EXPORT_CANCELLED".into());
+                }
+            }
+        }
+    }
+
+    // 3. 拼接视频片段并与音频封装
+    progress.report(ProgressEvent::export_with_eta(95.0, "正在进行最后的无损合并...", 1.0));
+    let list_path = temp_dir.join("list.txt");
+    let mut list_file = fs::File::create(&list_path)?;
+    for index in 0..pieces.len() {
+        writeln!(list_file, "file 'piece_{}.ts'", index)?;
+    }
+    list_file.flush()?;
+
+    let mut args: Vec<String> = vec![
+        "-nostdin".into(),
+        "-f".into(), "concat".into(),
+        "-safe".into(), "0".into(),
+        "-i".into(), list_path.to_string_lossy().to_string(),
+    ];
+    if has_audio {
+        args.extend(["-i".into(), audio_path.to_string_lossy().to_string()]);
+        args.extend(["-map".into(), "0:v".into(), "-map".into(), "1:a".into()]);
+    } else {
+        args.extend(["-map".into(), "0:v".into()]);
+    }
+    args.extend([
+        "-c".into(), "copy".into(),
+        "-avoid_negative_ts".into(), "make_zero".into(),
+    ]);
+    // 以下均为 MP4/MOV 封装选项
+    if super::wants_faststart(output, None) {
+        args.extend(["-movflags".into(), "+faststart".into()]);
+        if let Some(timescale) = source.timescale {
+            args.extend(["-video_track_timescale".into(), timescale.to_string()]);
+        }
+    }
+    args.extend(["-y".into(), output.to_string()]);
+    run_ffmpeg(ffmpeg_path, &args).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// 单个视频片段的 ffmpeg 参数
+fn piece_args(input: &str, piece: &Piece, source: &SourceVideo, output: &Path) -> Vec<String> {
+    let mut args: Vec<String> = vec!["-nostdin".into()];
+    match piece.kind {
+        PieceKind::Copy => {
+            // 拷贝时 -ss 会落到不晚于该时间的关键帧，稍微后移避免浮点误差落到上一个 GOP；
+            // 时长稍微缩短，不带上结尾处的关键帧 (它属于尾部编码段)
+            args.extend([
+                "-ss".into(), format!("{:.6}", piece.start + ALIGN_EPSILON),
+                "-i".into(), input.to_string(),
+                "-t".into(), format!("{:.6}", (piece.end - piece.start - 2.0 * ALIGN_EPSILON).max(0.0)),
+                "-map".into(), "0:v:0".into(),
+                "-an".into(),
+                "-c".into(), "copy".into(),
+            ]);
+        }
+        PieceKind::Encode => {
+            args.extend([
+                "-ss".into(), format!("{:.6}", piece.start),
+                "-i".into(), input.to_string(),
+                "-t".into(), format!("{:.6}", piece.end - piece.start),
+                "-map".into(), "0:v:0".into(),
+                "-an".into(),
+                "-c:v".into(), source.encoder.to_string(),
+            ]);
+            if let Some(profile) = source.profile {
+                args.extend(["-profile:v".into(), profile.to_string()]);
+            }
+            args.extend([
+                "-pix_fmt".into(), source.pix_fmt.clone(),
+                "-s".into(), format!("{}x{}", source.width, source.height),
+            ]);
+            if let Some(sar) = &source.sample_aspect_ratio {
+                args.extend(["-vf".into(), format!("setsar={}", sar.replace(':', "/"))]);
+            }
+            if let Some(rate) = &source.frame_rate {
+                args.extend(["-r".into(), rate.clone()]);
+            }
+            // 每个关键帧前重复 SPS/PPS，拼接后解码器在任意片段边界都能拿到当前参数集
+            match source.encoder {
+                "libx264" => {
+                    if let Some(level) = &source.level {
+                        args.extend(["-level:v".into(), level.clone()]);
+                    }
+                    args.extend(["-x264-params".into(), "repeat-headers=1".into()]);
+                }
+                "libx265" => {
+                    let mut params = "repeat-headers=1".to_string();
+                    if let Some(level) = &source.level {
+                        params.push_str(&format!(":level-idc={}", level));
+                    }
+                    args.extend(["-x265-params".into(), params]);
+                }
+                _ => {}
+            }
+            // 片段很短，用高质量参数避免与拷贝部分出现明显画质差
+            if source.encoder == "mpeg2video" {
+                args.extend(["-q:v".into(), "2".into()]);
+            } else {
+                args.extend(["-crf".into(), "18".into(), "-preset".into(), "veryfast".into()]);
+            }
+        }
+    }
+    args.extend(["-f".into(), "mpegts".into(), "-y".into(), output.to_string_lossy().to_string()]);
+    args
+}
+
+// 音频按所有保留片段精确裁剪后拼接编码
+fn audio_args(input: &str, filter_args: Vec<String>, output: &Path) -> Vec<String> {
+    let mut args: Vec<String> = vec!["-nostdin".into(), "-i".into(), input.to_string()];
+    args.extend(filter_args);
+    args.extend([
+        "-map".into(), "[fa]".into(),
+        "-c:a".into(), "aac".into(),
+        "-b:a".into(), "192k".into(),
+        "-y".into(), output.to_string_lossy().to_string(),
+    ]);
+    args
+}
+
+async fn run_ffmpeg(ffmpeg_path: &str, args: &[String]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let output = TokioCommand::new(ffmpeg_path)
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last_line = stderr.lines().last().unwrap_or("");
+        return Err(format!("FFmpeg 智能渲染失败: {}", last_line).into());
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(segments: &[(f64, f64)], keyframes: &[f64]) -> Vec<(PieceKind, f64, f64)> {
+        let segments: Vec<SpeechSegment> = segments.iter().map(|&(s, e)| SpeechSegment::new(s, e)).collect();
+        plan_pieces(&segments, keyframes).into_iter().map(|p| (p.kind, p.start, p.end)).collect()
+    }
+
+    #[test]
+    fn cut_on_keyframes_is_copied_whole() {
+        let pieces = plan(&[(2.0, 6.0)], &[0.0, 2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(pieces, vec![(PieceKind::Copy, 2.0, 6.0)]);
+    }
+
+    #[test]
+    fn cut_within_epsilon_counts_as_on_keyframe() {
+        let pieces = plan(&[(1.9995, 6.0005)], &[0.0, 2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(pieces, vec![(PieceKind::Copy, 2.0, 6.0)]);
+    }
+
+    #[test]
+    fn cut_inside_gop_encodes_partial_gops() {
+        let pieces = plan(&[(1.0, 6.0)], &[0.0, 2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(pieces, vec![(PieceKind::Encode, 1.0, 2.0), (PieceKind::Copy, 2.0, 6.0)]);
+
+        let pieces = plan(&[(1.0, 7.0)], &[0.0, 2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(pieces, vec![
+            (PieceKind::Encode, 1.0, 2.0),
+            (PieceKind::Copy, 2.0, 6.0),
+            (PieceKind::Encode, 6.0, 7.0),
+        ]);
+    }
+
+    #[test]
+    fn adjacent_cuts_in_one_gop_are_encoded_separately() {
+        let pieces = plan(&[(0.5, 1.0), (1.2, 1.8)], &[0.0, 2.0, 4.0]);
+        assert_eq!(pieces, vec![(PieceKind::Encode, 0.5, 1.0), (PieceKind::Encode, 1.2, 1.8)]);
+    }
+
+    #[test]
+    fn short_copy_span_is_encoded_whole() {
+        let pieces = plan(&[(1.5, 2.8)], &[0.0, 2.0, 2.5, 4.0]);
+        assert_eq!(pieces, vec![(PieceKind::Encode, 1.5, 2.8)]);
+    }
+}
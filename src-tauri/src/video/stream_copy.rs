@@ -0,0 +1,235 @@
+// 关键帧对齐的无损导出
+// 用 ffprobe 读出视频关键帧位置，把每个保留片段向外扩展到关键帧边界，
+// 再通过 concat demuxer 的 inpoint/outpoint 以 -c copy 拼接，全程不重新编码
+
+use super::{CutAdjustment, RenderContext, SpeechSegment};
+use std::io::Write;
+use std::process::Stdio;
+use std::sync::atomic::Ordering;
+use crate::utils::progress::ProgressEvent;
+use tempfile::NamedTempFile;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command as TokioCommand;
+
+// 时间比较容差，避免浮点误差把恰好落在关键帧上的剪辑点推到下一个 GOP
+const SNAP_EPSILON: f64 = 0.001;
+
+// 读取第一条视频流的关键帧时间 (只读包头，不解码)
+pub async fn probe_keyframes(ffprobe_path: &str, input: &str) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+    let output = TokioCommand::new(ffprobe_path)
+        .args([
+            "-v", "error",
+            "-select_streams", "v:0",
+            "-show_entries", "packet=pts_time,flags",
+            "-of", "csv=print_section=0",
+            input,
+        ])
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("读取关键帧失败: {}", err).into());
+    }
+
+    let mut keyframes: Vec<f64> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pts, flags) = line.split_once(',')?;
+            if !flags.contains('K') {
+                return None;
+            }
+            pts.trim().parse::<f64>().ok()
+        })
+        .collect();
+    keyframes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    keyframes.dedup();
+
+    log::info!("关键帧数量: {}", keyframes.len());
+    Ok(keyframes)
+}
+
+// 起点向前对齐到关键帧，终点向后对齐到关键帧 (只会多保留，不会切掉语音)
+// 对齐后重叠的片段合并；返回合并后的片段和每个原始片段的偏移量
+pub fn snap_to_keyframes(
+    segments: &[SpeechSegment],
+    keyframes: &[f64],
+    duration: f64,
+) -> (Vec<SpeechSegment>, Vec<CutAdjustment>) {
+    let mut snapped: Vec<SpeechSegment> = Vec::with_capacity(segments.len());
+    let mut adjustments = Vec::with_capacity(segments.len());
+
+    for segment in segments {
+        let start = if keyframes.is_empty() {
+            segment.start
+        } else {
+            keyframes.iter()
+                .rev()
+                .find(|&&k| k <= segment.start + SNAP_EPSILON)
+                .copied()
+                .unwrap_or(0.0)
+        };
+        let end = if keyframes.is_empty() {
+            segment.end
+        } else {
+            keyframes.iter()
+                .find(|&&k| k >= segment.end - SNAP_EPSILON)
+                .copied()
+                .unwrap_or(duration)
+        };
+
+        adjustments.push(CutAdjustment {
+            requested_start: segment.start,
+            requested_end: segment.end,
+            start_shift: start - segment.start,
+            end_shift: end - segment.end,
+        });
+
+        match snapped.last_mut() {
+            Some(last) if start <= last.end + SNAP_EPSILON => last.end = last.end.max(end),
+            _ => snapped.push(SpeechSegment::new(start, end)),
+        }
+    }
+
+    (snapped, adjustments)
+}
+
+// 用 concat demuxer 拼接各片段 (-c copy)
+pub async fn concat_copy(ctx: &RenderContext<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    let RenderContext { ffmpeg_path, input, output, segments, progress, cancel_signal } = *ctx;
+    let absolute_input = std::fs::canonicalize(input)?;
+    let escaped_input = absolute_input.to_string_lossy().replace('\'', "'\\''");
+
+    let mut list_file = NamedTempFile::new()?;
+    writeln!(list_file, "ffconcat version 1.0")?;
+    for segment in segments {
+        writeln!(list_file, "file '{}'", escaped_input)?;
+        writeln!(list_file, "inpoint {:.6}", segment.start)?;
+        writeln!(list_file, "outpoint {:.6}", segment.end)?;
+    }
+    list_file.flush()?;
+
+    let total_duration: f64 = segments.iter().map(|s| s.end - s.start).sum();
+    log::info!("无损拼接 {} 个片段, 输出时长约 {:.2}s", segments.len(), total_duration);
+
+    let mut command = TokioCommand::new(ffmpeg_path);
+    command.args([
+        "-nostdin",
+        "-f", "concat",
+        "-safe", "0",
+        "-i", list_file.path().to_str().ok_or("临时文件路径无效")?,
+        "-map", "0:v?",
+        "-map", "0:a?",
+        "-c", "copy",
+        "-avoid_negative_ts", "make_zero",
+    ]);
+    if super::wants_faststart(output, None) {
+        command.args(["-movflags", "+faststart"]);
+    }
+    let mut child = command
+        .args([
+            "-progress", "pipe:1",
+            "-nostats",
+            "-y",
+            output,
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let stdout = child.stdout.take().ok_or("无法打开 ffmpeg stdout")?;
+    let mut lines = BufReader::new(stdout).lines();
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let line = match line? {
+                    Some(line) => line,
+                    None => break,
+                };
+                // out_time_us 为已输出的时长 (微秒)
+                if let Some(value) = line.strip_prefix("out_time_us=") {
+                    if let Ok(us) = value.trim().parse::<f64>() {
+                        let done = us / 1_000_000.0;
+                        let percent = 5.0 + (done / total_duration.max(0.001)).min(1.0) * 90.0;
+                        progress.report(ProgressEvent::export_with_eta(
+                            percent,
+                            format!("正在无损拼接: {:.0}s / {:.0}s", done, total_duration),
+                            0.0,
+                        ));
+                    }
+                }
+            }
+            _ = tokio::time::sleep(tokio::time::Duration::from_millis(100)) => {
+                if cancel_signal.load(Ordering::SeqCst) {
+                    let _ = child.kill().await;
+                    let _ = std::fs::remove_file(output);
+                    log::warn!("任务被用户取消，已停止无损拼接");
+                    return Err("EXPORT_CANCELLED".into());
+                }
+            }
+        }
+    }
+
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err("无损拼接失败".into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(segments: &[SpeechSegment]) -> Vec<(f64, f64)> {
+        segments.iter().map(|s| (s.start, s.end)).collect()
+    }
+
+    #[test]
+    fn cut_within_epsilon_of_keyframe_stays_on_it() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0];
+        let segments = [SpeechSegment::new(1.9995, 4.0005)];
+        let (snapped, adjustments) = snap_to_keyframes(&segments, &keyframes, 8.0);
+        assert_eq!(spans(&snapped), vec![(2.0, 4.0)]);
+        assert!((adjustments[0].start_shift - 0.0005).abs() < 1e-9);
+        assert!((adjustments[0].end_shift + 0.0005).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cut_just_past_epsilon_moves_outward() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0];
+        let segments = [SpeechSegment::new(1.998, 4.002)];
+        let (snapped, adjustments) = snap_to_keyframes(&segments, &keyframes, 8.0);
+        assert_eq!(spans(&snapped), vec![(0.0, 6.0)]);
+        assert_eq!(adjustments[0].requested_start, 1.998);
+        assert!((adjustments[0].start_shift + 1.998).abs() < 1e-9);
+        assert!((adjustments[0].end_shift - 1.998).abs() < 1e-9);
+    }
+
+    #[test]
+    fn segments_sharing_a_gop_merge() {
+        let keyframes = [0.0, 2.0, 4.0, 6.0, 8.0];
+        let segments = [SpeechSegment::new(1.0, 3.0), SpeechSegment::new(4.5, 5.0), SpeechSegment::new(6.5, 7.0)];
+        let (snapped, adjustments) = snap_to_keyframes(&segments, &keyframes, 9.0);
+        // [0,4] 与 [4,6] 首尾相接合并，[6,8] 与之相接同样合并
+        assert_eq!(spans(&snapped), vec![(0.0, 8.0)]);
+        assert_eq!(adjustments.len(), 3);
+    }
+
+    #[test]
+    fn end_after_last_keyframe_extends_to_duration() {
+        let keyframes = [0.0, 2.0];
+        let segments = [SpeechSegment::new(2.5, 3.0)];
+        let (snapped, _) = snap_to_keyframes(&segments, &keyframes, 5.0);
+        assert_eq!(spans(&snapped), vec![(2.0, 5.0)]);
+    }
+
+    #[test]
+    fn without_keyframes_segments_are_unchanged() {
+        let segments = [SpeechSegment::new(1.0, 2.0), SpeechSegment::new(3.0, 4.0)];
+        let (snapped, adjustments) = snap_to_keyframes(&segments, &[], 5.0);
+        assert_eq!(spans(&snapped), vec![(1.0, 2.0), (3.0, 4.0)]);
+        assert!(adjustments.iter().all(|a| a.start_shift == 0.0 && a.end_shift == 0.0));
+    }
+}
@@ -0,0 +1,305 @@
+// 剪辑点过渡效果
+// 微淡入淡出：剪辑点两侧各自淡出/淡入，消除硬切产生的爆音
+// 交叉淡化：相邻片段各向静音区多取半个过渡时长再等功率叠化，输出总时长与硬切一致，音画保持同步
+// 视频叠化同理，用 xfade 在剪辑点处做短暂溶解。
+// 快进的片段先变速再参与过渡，过渡时长均按输出时间计算
+
+use super::SpeechSegment;
+use serde::{Deserialize, Serialize};
+
+// 过渡时长上限
+const MAX_TRANSITION_MS: u32 = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AudioTransition {
+    // 硬切 (旧行为)
+    #[default]
+    None,
+    #[serde(rename_all = "camelCase")]
+    Fade { duration_ms: u32 },
+    // 等功率交叉淡化 (四分之一正弦曲线)
+    #[serde(rename_all = "camelCase")]
+    Crossfade { duration_ms: u32 },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransitionOptions {
+    pub audio: AudioTransition,
+    // 视频叠化时长，为空时画面硬切
+    pub video_dissolve_ms: Option<u32>,
+}
+
+impl TransitionOptions {
+    pub fn is_none(&self) -> bool {
+        self.audio == AudioTransition::None && self.video_dissolve_ms.is_none()
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let audio_ms = match self.audio {
+            AudioTransition::None => None,
+            AudioTransition::Fade { duration_ms } | AudioTransition::Crossfade { duration_ms } => Some(duration_ms),
+        };
+        for ms in [audio_ms, self.video_dissolve_ms].into_iter().flatten() {
+            if ms == 0 || ms > MAX_TRANSITION_MS {
+                return Err(format!("过渡时长必须在 1 ~ {} ms 之间", MAX_TRANSITION_MS).into());
+            }
+        }
+        Ok(())
+    }
+
+    // 视频叠化时长 (秒)
+    pub fn video_dissolve(&self) -> Option<f64> {
+        self.video_dissolve_ms.map(|ms| ms as f64 / 1000.0)
+    }
+}
+
+// 每个剪辑点两侧片段向外延伸的输出时长，不超过两侧片段各自输出长度的一半，
+// 这样每个片段的淡入与淡出区间不会重叠
+fn join_pads(segments: &[SpeechSegment], half: f64) -> Vec<f64> {
+    segments.windows(2)
+        .map(|pair| half.min(pair[0].output_duration() / 2.0).min(pair[1].output_duration() / 2.0))
+        .collect()
+}
+
+// 按剪辑点延伸后的取样区间 (原文件时间，快进片段按倍速换算)
+fn padded_ranges(segments: &[SpeechSegment], pads: &[f64], duration: f64) -> Vec<(f64, f64)> {
+    segments.iter().enumerate().map(|(i, seg)| {
+        let start = if i > 0 { (seg.start - pads[i - 1] * seg.speed).max(0.0) } else { seg.start };
+        let end = if i < pads.len() { (seg.end + pads[i] * seg.speed).min(duration) } else { seg.end };
+        (start, end)
+    }).collect()
+}
+
+// 视频变速
+fn setpts(speed: f64) -> String {
+    if speed == 1.0 {
+        "setpts=PTS-STARTPTS".to_string()
+    } else {
+        format!("setpts=(PTS-STARTPTS)/{:.6}", speed)
+    }
+}
+
+// 音频变速，atempo 单级最高 2 倍，超出时串联多级
+fn atempo(speed: f64) -> String {
+    let mut filter = String::new();
+    if speed == 1.0 {
+        return filter;
+    }
+    let mut rest = speed;
+    while rest > 2.0 {
+        filter.push_str(",atempo=2.0");
+        rest /= 2.0;
+    }
+    filter.push_str(&format!(",atempo={:.6}", rest));
+    filter
+}
+
+// 视频滤镜链，输出标签为 [fv]；seek_start 为输入端 -ss 的偏移
+pub fn video_filter(segments: &[SpeechSegment], dissolve: Option<f64>, duration: f64, seek_start: f64) -> String {
+    let mut filter = String::new();
+    match dissolve {
+        None => {
+            let mut concat = String::new();
+            for (i, seg) in segments.iter().enumerate() {
+                filter.push_str(&format!("[0:v]trim=start={:.6}:duration={:.6},{}[v{}];", (seg.start - seek_start).max(0.0), seg.end - seg.start, setpts(seg.speed), i));
+                concat.push_str(&format!("[v{}]", i));
+            }
+            filter.push_str(&format!("{}concat=n={}:v=1:a=0[fv]", concat, segments.len()));
+        }
+        Some(dissolve) => {
+            let pads = join_pads(segments, dissolve / 2.0);
+            let ranges = padded_ranges(segments, &pads, duration);
+            filter = xfade_chain(segments, &ranges, &pads, seek_start);
+        }
+    }
+    filter
+}
+
+// 按给定取样区间截取各片段，并在相邻片段之间叠化，输出标签为 [fv]
+fn xfade_chain(segments: &[SpeechSegment], ranges: &[(f64, f64)], pads: &[f64], seek_start: f64) -> String {
+    let mut filter = String::new();
+    let output_lens: Vec<f64> = ranges.iter().zip(segments).map(|((start, end), seg)| (end - start) / seg.speed).collect();
+    for (i, ((start, end), seg)) in ranges.iter().zip(segments).enumerate() {
+        filter.push_str(&format!("[0:v]trim=start={:.6}:end={:.6},{}[v{}];", (start - seek_start).max(0.0), end - seek_start, setpts(seg.speed), i));
+    }
+    // offset 为叠化开始时已输出的时长
+    let mut output_len = output_lens[0];
+    let mut last = "v0".to_string();
+    for (i, pad) in pads.iter().enumerate() {
+        let overlap = pad * 2.0;
+        let label = format!("vx{}", i + 1);
+        filter.push_str(&format!("[{}][v{}]xfade=transition=fade:duration={:.6}:offset={:.6}[{}];", last, i + 1, overlap, (output_len - overlap).max(0.0), label));
+        output_len += output_lens[i + 1] - overlap;
+        last = label;
+    }
+    filter.push_str(&format!("[{}]null[fv]", last));
+    filter
+}
+
+// 分批渲染视频时的一部分，seek_start 为输入端 -ss 的位置
+#[derive(Debug, Clone)]
+pub struct VideoPart {
+    pub seek_start: f64,
+    pub filter: String,
+}
+
+// 视频叠化分批：每批 batch_size 个片段，批次内部的剪辑点在各自的滤镜图中叠化；
+// 批次之间的剪辑点单独渲染一段过渡 (前一批末尾与后一批开头各让出叠化所需的画面)，
+// 按顺序拼接后与整体渲染的时间轴一致
+pub fn dissolve_parts(segments: &[SpeechSegment], dissolve: f64, duration: f64, batch_size: usize) -> Vec<VideoPart> {
+    let pads = join_pads(segments, dissolve / 2.0);
+    let ranges = padded_ranges(segments, &pads, duration);
+    let batch_size = batch_size.max(2);
+    let mut parts = Vec::new();
+
+    let mut first = 0;
+    while first < segments.len() {
+        let end = (first + batch_size).min(segments.len());
+        let mut batch_ranges = ranges[first..end].to_vec();
+        if first > 0 {
+            batch_ranges[0].0 += 2.0 * pads[first - 1] * segments[first].speed;
+        }
+        if end < segments.len() {
+            batch_ranges[end - first - 1].1 -= 2.0 * pads[end - 1] * segments[end - 1].speed;
+        }
+        let seek_start = batch_ranges[0].0;
+        parts.push(VideoPart {
+            seek_start,
+            filter: xfade_chain(&segments[first..end], &batch_ranges, &pads[first..end - 1], seek_start),
+        });
+
+        if end < segments.len() {
+            // 前一片段最后 overlap 秒与后一片段最初 overlap 秒 (输出时长) 叠化
+            let (before, after) = (&segments[end - 1], &segments[end]);
+            let overlap = 2.0 * pads[end - 1];
+            let tail = (batch_ranges[end - first - 1].1, ranges[end - 1].1);
+            let head = (ranges[end].0, ranges[end].0 + overlap * after.speed);
+            let seek_start = tail.0.min(head.0);
+            parts.push(VideoPart {
+                seek_start,
+                filter: format!(
+                    "[0:v]trim=start={:.6}:end={:.6},{}[va];[0:v]trim=start={:.6}:end={:.6},{}[vb];[va][vb]xfade=transition=fade:duration={:.6}:offset=0[fv]",
+                    tail.0 - seek_start, tail.1 - seek_start, setpts(before.speed),
+                    head.0 - seek_start, head.1 - seek_start, setpts(after.speed),
+                    overlap,
+                ),
+            });
+        }
+        first = end;
+    }
+    parts
+}
+
+// 音频滤镜链，输出标签为 [fa]；input 为输入音频流标签 (如 0:a)
+pub fn audio_filter(segments: &[SpeechSegment], transition: AudioTransition, duration: f64, seek_start: f64, input: &str) -> String {
+    let mut filter = String::new();
+    match transition {
+        AudioTransition::None | AudioTransition::Fade { .. } => {
+            let fade = match transition {
+                AudioTransition::Fade { duration_ms } => duration_ms as f64 / 1000.0,
+                _ => 0.0,
+            };
+            let mut concat = String::new();
+            for (i, seg) in segments.iter().enumerate() {
+                let len = seg.output_duration();
+                filter.push_str(&format!("[{}]atrim=start={:.6}:duration={:.6},asetpts=PTS-STARTPTS{}", input, (seg.start - seek_start).max(0.0), seg.end - seg.start, atempo(seg.speed)));
+                // 文件首尾不是剪辑点，不做淡化
+                let d = fade.min(len / 2.0);
+                if d > 0.0 && i > 0 {
+                    filter.push_str(&format!(",afade=t=in:st=0:d={:.6}", d));
+                }
+                if d > 0.0 && i + 1 < segments.len() {
+                    filter.push_str(&format!(",afade=t=out:st={:.6}:d={:.6}", len - d, d));
+                }
+                filter.push_str(&format!("[a{}];", i));
+                concat.push_str(&format!("[a{}]", i));
+            }
+            filter.push_str(&format!("{}concat=n={}:v=0:a=1[fa]", concat, segments.len()));
+        }
+        AudioTransition::Crossfade { duration_ms } => {
+            let pads = join_pads(segments, duration_ms as f64 / 2000.0);
+            let ranges = padded_ranges(segments, &pads, duration);
+            for (i, ((start, end), seg)) in ranges.iter().zip(segments).enumerate() {
+                filter.push_str(&format!("[{}]atrim=start={:.6}:end={:.6},asetpts=PTS-STARTPTS{}[a{}];", input, (start - seek_start).max(0.0), end - seek_start, atempo(seg.speed), i));
+            }
+            let mut last = "a0".to_string();
+            for (i, pad) in pads.iter().enumerate() {
+                let label = format!("ax{}", i + 1);
+                filter.push_str(&format!("[{}][a{}]acrossfade=d={:.6}:c1=qsin:c2=qsin[{}];", last, i + 1, pad * 2.0, label));
+                last = label;
+            }
+            filter.push_str(&format!("[{}]anull[fa]", last));
+        }
+    }
+    filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(spans: &[(f64, f64)]) -> Vec<SpeechSegment> {
+        spans.iter().map(|&(start, end)| SpeechSegment::new(start, end)).collect()
+    }
+
+    #[test]
+    fn hard_cut_trims_relative_to_seek_start() {
+        let filter = video_filter(&segments(&[(10.0, 12.0), (13.0, 14.5)]), None, 20.0, 10.0);
+        assert_eq!(filter, "[0:v]trim=start=0.000000:duration=2.000000,setpts=PTS-STARTPTS[v0];\
+            [0:v]trim=start=3.000000:duration=1.500000,setpts=PTS-STARTPTS[v1];\
+            [v0][v1]concat=n=2:v=1:a=0[fv]");
+    }
+
+    #[test]
+    fn dissolve_is_limited_by_neighbour_length() {
+        // 第二段只有 0.2 秒，叠化时两侧各延伸 0.1 秒
+        let filter = video_filter(&segments(&[(0.0, 1.0), (2.0, 2.2)]), Some(1.0), 10.0, 0.0);
+        assert!(filter.contains("[0:v]trim=start=0.000000:end=1.100000"));
+        assert!(filter.contains("[0:v]trim=start=1.900000:end=2.200000"));
+        assert!(filter.contains("xfade=transition=fade:duration=0.200000:offset=0.900000[vx1]"));
+    }
+
+    #[test]
+    fn fades_skip_file_start_and_end() {
+        let filter = audio_filter(&segments(&[(0.0, 1.0), (2.0, 3.0), (4.0, 5.0)]), AudioTransition::Fade { duration_ms: 100 }, 5.0, 0.0, "0:a");
+        let chains: Vec<&str> = filter.split(';').collect();
+        assert!(!chains[0].contains("t=in") && chains[0].contains("afade=t=out:st=0.900000:d=0.100000"));
+        assert!(chains[1].contains("afade=t=in:st=0:d=0.100000") && chains[1].contains("t=out"));
+        assert!(chains[2].contains("t=in") && !chains[2].contains("t=out"));
+        assert!(chains[3].ends_with("concat=n=3:v=0:a=1[fa]"));
+    }
+
+    #[test]
+    fn crossfade_overlaps_padded_ranges() {
+        let filter = audio_filter(&segments(&[(0.0, 1.0), (2.0, 3.0)]), AudioTransition::Crossfade { duration_ms: 200 }, 5.0, 0.0, "0:a");
+        assert!(filter.contains("[0:a]atrim=start=0.000000:end=1.100000"));
+        assert!(filter.contains("[0:a]atrim=start=1.900000:end=3.000000"));
+        assert!(filter.contains("acrossfade=d=0.200000:c1=qsin:c2=qsin[ax1]"));
+    }
+
+    #[test]
+    fn dissolve_parts_bridge_batches() {
+        // 每批 2 段：[0,1] 一批、两批之间的过渡、[2] 一批
+        let parts = dissolve_parts(&segments(&[(0.0, 2.0), (3.0, 5.0), (6.0, 8.0)]), 0.4, 10.0, 2);
+        assert_eq!(parts.len(), 3);
+
+        // 第一批末尾让出 0.4 秒给过渡
+        assert_eq!(parts[0].seek_start, 0.0);
+        assert!(parts[0].filter.contains("trim=start=0.000000:end=2.200000"));
+        assert!(parts[0].filter.contains("trim=start=2.800000:end=4.800000"));
+        assert!(parts[0].filter.contains("xfade=transition=fade:duration=0.400000:offset=1.800000"));
+
+        // 过渡：第二段的 4.8-5.2 秒与第三段的 5.8-6.2 秒叠化
+        assert_eq!(parts[1].seek_start, 4.8);
+        assert!(parts[1].filter.contains("[0:v]trim=start=0.000000:end=0.400000"));
+        assert!(parts[1].filter.contains("[0:v]trim=start=1.000000:end=1.400000"));
+        assert!(parts[1].filter.ends_with("xfade=transition=fade:duration=0.400000:offset=0[fv]"));
+
+        // 第二批从过渡之后开始，三部分输出共 3.8 + 0.4 + 1.8 秒，与硬切总时长一致
+        assert!((parts[2].seek_start - 6.2).abs() < 1e-9);
+        assert!(parts[2].filter.starts_with("[0:v]trim=start=0.000000:end=1.800000"));
+        assert!(parts[2].filter.ends_with("[v0]null[fv]"));
+    }
+}
@@ -0,0 +1,485 @@
+// src-tauri/src/watch/mod.rs
+// 监视文件夹自动处理
+// 定时扫描输入目录，新出现的视频在文件大小稳定一段时间后 (录制/拷贝完成) 按保存的预设提交到导出任务队列。
+// 已提交的文件记录在处理台账中 (路径 + 大小 + 修改时间)，重启后不会重复处理
+
+use crate::jobs;
+use crate::video::ProcessSettings;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::Emitter;
+
+const DEFAULT_EXTENSIONS: &[&str] = &["mp4", "mov", "mkv", "m4v", "webm", "avi", "flv", "ts"];
+
+fn default_poll_interval() -> f64 {
+    5.0
+}
+
+fn default_stable_time() -> f64 {
+    10.0
+}
+
+fn default_extensions() -> Vec<String> {
+    DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect()
+}
+
+// 监视配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchConfig {
+    pub input_dir: String,
+    pub output_dir: String,
+    #[serde(default)]
+    pub settings: ProcessSettings,
+    // 扫描间隔 (秒)
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: f64,
+    // 文件大小保持不变超过该时长 (秒) 才认为写入完成
+    #[serde(default = "default_stable_time")]
+    pub stable_time: f64,
+    // 处理的扩展名 (小写，不带点)
+    #[serde(default = "default_extensions")]
+    pub extensions: Vec<String>,
+    // 是否处理开始监视前目录中已有的文件
+    #[serde(default)]
+    pub process_existing: bool,
+}
+
+// 正在运行的监视
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolder {
+    pub id: String,
+    pub config: WatchConfig,
+}
+
+// 处理台账中的一条记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerEntry {
+    pub watch_id: String,
+    pub path: String,
+    pub size: u64,
+    pub modified: u64,
+    pub job_id: Option<String>,
+    pub error: Option<String>,
+    // 开始监视前已存在、按配置跳过的文件
+    #[serde(default)]
+    pub skipped: bool,
+    pub queued_at: String,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum WatchEventKind {
+    // 发现新文件，等待写入完成
+    Detected,
+    // 已提交到任务队列
+    Queued,
+    // 提交失败
+    Failed,
+    Started,
+    Stopped,
+}
+
+// 发送给前端的监视事件 (watch-event)
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchEvent {
+    pub watch_id: String,
+    pub kind: WatchEventKind,
+    pub path: Option<String>,
+    pub job_id: Option<String>,
+    pub message: Option<String>,
+}
+
+struct ActiveWatch {
+    folder: WatchFolder,
+    stop: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct WatchState {
+    watches: HashMap<String, ActiveWatch>,
+    ledger: Vec<LedgerEntry>,
+    store_dir: Option<PathBuf>,
+    app: Option<tauri::AppHandle>,
+}
+
+static STATE: Lazy<Mutex<WatchState>> = Lazy::new(|| Mutex::new(WatchState::default()));
+
+impl WatchState {
+    fn save(&self) {
+        let dir = match &self.store_dir {
+            Some(d) => d,
+            None => return,
+        };
+        let folders: Vec<&WatchFolder> = self.watches.values().map(|w| &w.folder).collect();
+        write_json(&dir.join("watch_folders.json"), &folders);
+        write_json(&dir.join("watch_ledger.json"), &self.ledger);
+    }
+
+    fn emit(&self, event: WatchEvent) {
+        if let Some(app) = &self.app {
+            let _ = app.emit("watch-event", event);
+        }
+    }
+
+    // 已处理的文件按路径、大小与修改时间匹配，内容变化后重新处理；
+    // 开始监视时跳过的文件只按路径匹配，当时仍在复制中的文件写完后也不会被处理
+    fn is_processed(&self, watch_id: &str, path: &str, size: u64, modified: u64) -> bool {
+        self.ledger.iter().any(|e| {
+            e.path == path && if e.skipped { e.watch_id == watch_id } else { e.size == size && e.modified == modified }
+        })
+    }
+}
+
+// 先写临时文件再重命名
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) {
+    let temp_path = path.with_extension("tmp");
+    let result = serde_json::to_vec_pretty(value)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| fs::write(&temp_path, bytes).map_err(|e| e.to_string()))
+        .and_then(|_| fs::rename(&temp_path, path).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("保存监视配置失败 {:?}: {}", path, e);
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned + Default>(path: &Path) -> T {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+// 应用启动时调用：读取台账并恢复上次的监视，store_dir 为空时不持久化
+pub fn init(app: tauri::AppHandle, store_dir: Option<PathBuf>) {
+    let (folders, mut ledger): (Vec<WatchFolder>, Vec<LedgerEntry>) = match &store_dir {
+        Some(dir) => (read_json(&dir.join("watch_folders.json")), read_json(&dir.join("watch_ledger.json"))),
+        None => (Vec::new(), Vec::new()),
+    };
+    // 文件已被删除或移走的记录不会再匹配，丢弃以免台账无限增长
+    let before = ledger.len();
+    ledger.retain(|e| Path::new(&e.path).exists());
+    let pruned = before - ledger.len();
+
+    {
+        let mut state = STATE.lock().unwrap();
+        state.ledger = ledger;
+        state.store_dir = store_dir;
+        state.app = Some(app);
+        if pruned > 0 {
+            log::info!("清理监视台账: {} 条记录对应的文件已不存在", pruned);
+            state.save();
+        }
+    }
+
+    for folder in folders {
+        if let Err(e) = start_folder(folder.clone(), false) {
+            log::warn!("恢复文件夹监视失败 {}: {}", folder.config.input_dir, e);
+        }
+    }
+}
+
+// 开始监视一个文件夹
+pub fn start(config: WatchConfig) -> Result<WatchFolder, String> {
+    let folder = WatchFolder {
+        id: format!("watch_{}_{:08x}", chrono::Local::now().timestamp_millis(), rand::random::<u32>()),
+        config,
+    };
+    start_folder(folder.clone(), !folder.config.process_existing)?;
+    Ok(folder)
+}
+
+// 把目录中已有的文件记入台账，只处理之后新增的文件
+fn skip_existing(folder: &WatchFolder) {
+    let entries = match fs::read_dir(&folder.config.input_dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    let mut state = STATE.lock().unwrap();
+    for entry in entries.flatten() {
+        let (path, size, modified) = match file_info(&entry, &folder.config.extensions) {
+            Some(info) => info,
+            None => continue,
+        };
+        state.ledger.push(LedgerEntry {
+            watch_id: folder.id.clone(),
+            path,
+            size,
+            modified,
+            job_id: None,
+            error: None,
+            skipped: true,
+            queued_at: chrono::Local::now().to_rfc3339(),
+        });
+    }
+    state.save();
+}
+
+// 符合扩展名的文件返回 (路径, 大小, 修改时间)
+fn file_info(entry: &fs::DirEntry, extensions: &[String]) -> Option<(String, u64, u64)> {
+    let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+    let path = entry.path();
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    if !extensions.contains(&extension) {
+        return None;
+    }
+    let modified = metadata.modified().ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some((path.to_string_lossy().to_string(), metadata.len(), modified))
+}
+
+// skip_present 为 true 时先把目录中已有的文件记入台账，再开始扫描
+fn start_folder(folder: WatchFolder, skip_present: bool) -> Result<(), String> {
+    let config = &folder.config;
+    let input_dir = fs::canonicalize(&config.input_dir).map_err(|e| format!("监视目录无效: {}", e))?;
+    if !input_dir.is_dir() {
+        return Err(format!("监视目录不是文件夹: {}", config.input_dir));
+    }
+    fs::create_dir_all(&config.output_dir).map_err(|e| format!("创建输出目录失败: {}", e))?;
+    // 输出目录与监视目录相同时，导出的文件会被再次处理
+    if fs::canonicalize(&config.output_dir).ok().as_deref() == Some(input_dir.as_path()) {
+        return Err("输出目录不能与监视目录相同".to_string());
+    }
+    if config.poll_interval <= 0.0 || config.stable_time < 0.0 {
+        return Err("扫描间隔必须大于 0，稳定等待时间不能为负".to_string());
+    }
+
+    let mut state = STATE.lock().unwrap();
+    if state.watches.values().any(|w| fs::canonicalize(&w.folder.config.input_dir).ok().as_deref() == Some(input_dir.as_path())) {
+        return Err(format!("该目录已在监视中: {}", config.input_dir));
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    state.watches.insert(folder.id.clone(), ActiveWatch { folder: folder.clone(), stop: stop.clone() });
+    state.save();
+    state.emit(WatchEvent {
+        watch_id: folder.id.clone(),
+        kind: WatchEventKind::Started,
+        path: Some(config.input_dir.clone()),
+        job_id: None,
+        message: None,
+    });
+    drop(state);
+
+    // 必须在扫描开始前记入台账，否则首次扫描会把已有文件当作新文件
+    if skip_present {
+        skip_existing(&folder);
+    }
+
+    log::info!("开始监视文件夹: {} -> {}", config.input_dir, config.output_dir);
+    tauri::async_runtime::spawn(run_watch(folder, stop));
+    Ok(())
+}
+
+// 停止监视
+pub fn stop(id: &str) -> Result<(), String> {
+    let mut state = STATE.lock().unwrap();
+    let watch = state.watches.remove(id).ok_or_else(|| format!("监视不存在: {}", id))?;
+    watch.stop.store(true, Ordering::SeqCst);
+    state.save();
+    state.emit(WatchEvent {
+        watch_id: id.to_string(),
+        kind: WatchEventKind::Stopped,
+        path: Some(watch.folder.config.input_dir.clone()),
+        job_id: None,
+        message: None,
+    });
+    log::info!("停止监视文件夹: {}", watch.folder.config.input_dir);
+    Ok(())
+}
+
+pub fn list() -> Vec<WatchFolder> {
+    STATE.lock().unwrap().watches.values().map(|w| w.folder.clone()).collect()
+}
+
+// 处理台账，watch_id 为空时返回全部
+pub fn ledger(watch_id: Option<&str>) -> Vec<LedgerEntry> {
+    STATE.lock().unwrap().ledger.iter()
+        .filter(|e| watch_id.is_none() || watch_id == Some(e.watch_id.as_str()))
+        .cloned()
+        .collect()
+}
+
+// 等待写入完成的文件：上次扫描时的大小与大小最后一次变化的时间
+struct PendingFile {
+    size: u64,
+    changed_at: Instant,
+}
+
+impl PendingFile {
+    // 记录本次扫描到的大小，返回是否已写入完成 (非空且大小保持不变达到 stable_time)
+    fn observe(&mut self, size: u64, now: Instant, stable_time: Duration) -> bool {
+        if self.size != size {
+            self.size = size;
+            self.changed_at = now;
+        }
+        size > 0 && now.duration_since(self.changed_at) >= stable_time
+    }
+}
+
+async fn run_watch(folder: WatchFolder, stop: Arc<AtomicBool>) {
+    let config = folder.config;
+    let mut pending: HashMap<PathBuf, PendingFile> = HashMap::new();
+    let stable_time = Duration::from_secs_f64(config.stable_time);
+
+    while !stop.load(Ordering::SeqCst) {
+        let entries = match fs::read_dir(&config.input_dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("扫描监视目录失败 {}: {}", config.input_dir, e);
+                tokio::time::sleep(Duration::from_secs_f64(config.poll_interval)).await;
+                continue;
+            }
+        };
+
+        let now = Instant::now();
+        let mut seen = Vec::new();
+        for entry in entries.flatten() {
+            let (path_str, size, modified) = match file_info(&entry, &config.extensions) {
+                Some(info) => info,
+                None => continue,
+            };
+            let path = entry.path();
+            if STATE.lock().unwrap().is_processed(&folder.id, &path_str, size, modified) {
+                continue;
+            }
+            seen.push(path.clone());
+
+            let file = pending.entry(path.clone()).or_insert_with(|| {
+                STATE.lock().unwrap().emit(WatchEvent {
+                    watch_id: folder.id.clone(),
+                    kind: WatchEventKind::Detected,
+                    path: Some(path_str.clone()),
+                    job_id: None,
+                    message: None,
+                });
+                PendingFile { size, changed_at: now }
+            });
+            // 空文件或仍在写入的文件继续等待
+            if !file.observe(size, now, stable_time) {
+                continue;
+            }
+
+            pending.remove(&path);
+            submit_file(&folder.id, &config, &path_str, size, modified);
+        }
+        // 等待期间被删除的文件不再跟踪
+        pending.retain(|path, _| seen.contains(path));
+
+        tokio::time::sleep(Duration::from_secs_f64(config.poll_interval)).await;
+    }
+}
+
+// 提交到任务队列并记入台账 (提交失败也记录，避免每次扫描重复报错)
+fn submit_file(watch_id: &str, config: &WatchConfig, path: &str, size: u64, modified: u64) {
+    let output_path = crate::video::batch_output_path(&config.output_dir, path, config.settings.export.output_extension())
+        .to_string_lossy()
+        .to_string();
+    let result = jobs::submit(path.to_string(), output_path, config.settings.clone(), None);
+
+    let mut state = STATE.lock().unwrap();
+    let (job_id, error) = match result {
+        Ok(job) => {
+            log::info!("监视目录新文件已加入队列: {} ({})", path, job.id);
+            (Some(job.id), None)
+        }
+        Err(e) => {
+            log::warn!("监视目录文件提交失败 {}: {}", path, e);
+            (None, Some(e))
+        }
+    };
+    state.ledger.push(LedgerEntry {
+        watch_id: watch_id.to_string(),
+        path: path.to_string(),
+        size,
+        modified,
+        job_id: job_id.clone(),
+        error: error.clone(),
+        skipped: false,
+        queued_at: chrono::Local::now().to_rfc3339(),
+    });
+    state.save();
+    state.emit(WatchEvent {
+        watch_id: watch_id.to_string(),
+        kind: if error.is_some() { WatchEventKind::Failed } else { WatchEventKind::Queued },
+        path: Some(path.to_string()),
+        job_id,
+        message: error,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(watch_id: &str, path: &str, size: u64, skipped: bool) -> LedgerEntry {
+        LedgerEntry {
+            watch_id: watch_id.to_string(),
+            path: path.to_string(),
+            size,
+            modified: 100,
+            job_id: None,
+            error: None,
+            skipped,
+            queued_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn file_is_stable_after_size_holds() {
+        let start = Instant::now();
+        let stable = Duration::from_secs(10);
+        let mut file = PendingFile { size: 100, changed_at: start };
+
+        assert!(!file.observe(100, start + Duration::from_secs(5), stable));
+        assert!(file.observe(100, start + Duration::from_secs(10), stable));
+    }
+
+    #[test]
+    fn growing_file_restarts_wait() {
+        let start = Instant::now();
+        let stable = Duration::from_secs(10);
+        let mut file = PendingFile { size: 100, changed_at: start };
+
+        assert!(!file.observe(200, start + Duration::from_secs(8), stable));
+        assert!(!file.observe(200, start + Duration::from_secs(12), stable));
+        assert!(file.observe(200, start + Duration::from_secs(18), stable));
+    }
+
+    #[test]
+    fn empty_file_is_never_stable() {
+        let start = Instant::now();
+        let mut file = PendingFile { size: 0, changed_at: start };
+        assert!(!file.observe(0, start + Duration::from_secs(60), Duration::ZERO));
+        assert!(file.observe(10, start + Duration::from_secs(61), Duration::ZERO));
+    }
+
+    #[test]
+    fn processed_files_match_size_and_time() {
+        let state = WatchState { ledger: vec![entry("w1", "/in/a.mp4", 100, false)], ..WatchState::default() };
+        assert!(state.is_processed("w1", "/in/a.mp4", 100, 100));
+        assert!(state.is_processed("w2", "/in/a.mp4", 100, 100));
+        assert!(!state.is_processed("w1", "/in/a.mp4", 200, 100));
+        assert!(!state.is_processed("w1", "/in/a.mp4", 100, 101));
+    }
+
+    #[test]
+    fn skipped_files_match_by_path_within_watch() {
+        // 跳过时文件仍在复制，之后大小变化也不处理
+        let state = WatchState { ledger: vec![entry("w1", "/in/a.mp4", 10, true)], ..WatchState::default() };
+        assert!(state.is_processed("w1", "/in/a.mp4", 5000, 200));
+        assert!(!state.is_processed("w2", "/in/a.mp4", 5000, 200));
+        assert!(!state.is_processed("w1", "/in/b.mp4", 10, 100));
+    }
+}